@@ -49,6 +49,14 @@
 //! PREFIX |COMMAND| SUFFIX
 //! ```
 //!
+//! Suffixes can't contain pipes or line breaks directly. Write `\|` for a pipe and `\n` for a line break instead, and
+//! `\\` for a backslash that would otherwise start one of these escapes. A `//` after a space starts a trailing comment,
+//! so write `\//` to keep it in the suffix.
+//!
+//! ```text
+//! ZELDA |SAY| "Roses are red,\nViolets are blue \| purple."
+//! ```
+//!
 //! #### SAY
 //!
 //! The `|SAY|` command is used to display dialogue. When an optional prefix is supplied, it represents the
@@ -67,6 +75,21 @@
 //!
 //! The above command would display the text `It was a dark and stormy night...` in a dialogue box with no speaker.
 //!
+//! Several characters can speak in unison by separating their names with ` & `.
+//!
+//! ```text
+//! ZELDA & LINK |SAY| "Surprise!"
+//! ```
+//!
+//! Long passages can span several lines by wrapping them in triple quotes. The text is kept exactly as written.
+//!
+//! ```text
+//! NARRATOR |SAY| """
+//! It was a dark and stormy night.
+//! The rain fell in torrents.
+//! """
+//! ```
+//!
 //! #### CHOICE
 //!
 //! The `|CHOICE|` command is used to declare a list of choices. A suffix is required, and it represents a choice
@@ -84,6 +107,20 @@
 //! The above command would display a list of choices with the text `"Yes"` and `"No"`. If the player chose
 //! `"Yes"`, `PLAYER  - "Yes"` would be printed and the dialogue would continue at the `%CONTINUE%` marker. If the player chose `"No"`, `PLAYER - "No"` would be printed and the dialogue would continue at the `%GO-BACK%` marker.
 //!
+//! A choice can be given a stable key in square brackets before its text. Keys aren't shown to the player, and
+//! they stay the same when the text of a choice is reworded.
+//!
+//! ```text
+//! |CHOICE| [accept] "I accept"
+//! ```
+//!
+//! A choice can also be limited to when a condition holds, by writing `IF` and the condition after its text, like
+//! in a conditional `|GOTO|`.
+//!
+//! ```text
+//! |CHOICE| "Unlock the door" IF has_key == true
+//! ```
+//!
 //! #### GOTO
 //!
 //! The `|GOTO|` command is used to redirect the flow of dialogue to a marker. A suffix is required, and it
@@ -112,9 +149,44 @@
 //! In the above dialogue, if the player chose `What?`, the dialogue would loop back to the start.
 //! If the player chose `Yes`, the dialogue would continue to the end.
 //!
+//! A `|GOTO|` can be made conditional by adding `IF` and a condition after the marker. The condition compares a
+//! variable against a value, using one of `==`, `!=`, `<`, `<=`, `>`, or `>=`.
+//!
+//! ```text
+//! |GOTO| %SHORTCUT% IF flag == 1
+//! ```
+//!
+//! A second marker can be given as a fallback, for when the first one doesn't exist. This lets scripts keep working
+//! when a marker is removed.
+//!
+//! ```text
+//! |GOTO| %PREFERRED% %FALLBACK%
+//! ```
+//!
+//! #### INCLUDE
+//!
+//! The `|INCLUDE|` command is used to pull the body of another script into the current one. A suffix is required,
+//! and it's the path of the script to include, relative to the current script. A prefix is not allowed.
+//!
+//! ```text
+//! |INCLUDE| common/greetings.script
+//! ```
+//!
+//! The `%START%` and `%END%` markers of the included script are dropped, and the rest of it is inserted where the
+//! `|INCLUDE|` command was. Includes are only expanded by `Script::parse_with_includes`.
+//!
+//! #### ASSERT
+//!
+//! The `|ASSERT|` command is used to test a script. A suffix is required, and it's a condition that should hold
+//! whenever the command is reached. A prefix is not allowed.
+//!
+//! ```text
+//! |ASSERT| gold == 100
+//! ```
+//!
 //! ### Blocks
 //!
-//! Blocks are used to organize dialogue. They are indented by 4 spaces and can contain any number of lines or inner blocks. Blocks can be nested to any depth, though you should avoid nesting deeply, as it makes scripts difficult to read. The |CHOICE| and |GOTO| commands show examples of how blocks can be used. When a block is entered, dialogue will continue from the first line of the block. When a block is exited, dialogue will continue from the first line after the block.
+//! Blocks are used to organize dialogue. They are indented by 4 spaces or a tab, though a script must stick to one or the other, and can contain any number of lines or inner blocks. Blocks can be nested to any depth, though you should avoid nesting deeply, as it makes scripts difficult to read. The |CHOICE| and |GOTO| commands show examples of how blocks can be used. When a block is entered, dialogue will continue from the first line of the block. When a block is exited, dialogue will continue from the first line after the block.
 //!
 //! ```text
 //! %START%
@@ -148,6 +220,19 @@
 //! // This is a comment
 //! ```
 //!
+//! Commenting out a command disables it. The command is skipped, but it stays in the script so it can be enabled
+//! again later.
+//!
+//! ```text
+//! // ZELDA |SAY| "This line is disabled for now."
+//! ```
+//!
+//! A comment can also follow a command on the same line, as long as there's whitespace before the `//`.
+//!
+//! ```text
+//! ZELDA |SAY| "Hey there!" // First greeting
+//! ```
+//!
 //! ## License
 //!
 //! This project is licensed under the [MIT License](https://opensource.org/licenses/MIT).
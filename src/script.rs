@@ -1,23 +1,81 @@
 //! Script parsing and representation. Scripts are a collection of lines and blocks.
 
 pub mod block;
+pub mod choice_group;
 pub mod command;
+pub mod command_spec;
 pub mod comment;
+pub mod condition;
+pub mod diff;
 pub mod element;
+pub mod error;
+pub(crate) mod include;
 pub mod line;
 pub mod marker;
+pub(crate) mod mermaid;
 pub(crate) mod parser;
+pub mod subtitles;
+pub mod text_table;
 
-use self::{block::Block, element::TopLevelElement, line::Line};
-use anyhow::bail;
+use self::{
+    block::Block,
+    choice_group::ChoiceGroup,
+    command::Command,
+    comment::Comment,
+    diff::ElementChange,
+    element::TopLevelElement,
+    error::ScriptError,
+    line::Line,
+    marker::{is_valid_marker_name, Marker, END_MARKER, START_MARKER},
+};
+use anyhow::{bail, Context};
 use parser::{Parser, Rule};
 use pest::{iterators::Pair, Parser as PestParser};
-use std::fmt;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 /// A collection of lines and blocks, acting as a state machine for dialogue.
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Script(pub Vec<TopLevelElement>);
 
+/// A check that can be run on a parsed [Script], see [Script::parse_and_check_with].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Check {
+    /// See [Script::check_goto_targets].
+    GotoTargets,
+    /// See [Script::check_duplicate_choices].
+    DuplicateChoices,
+    /// See [Script::check_choice_bodies].
+    ChoiceBodies,
+    /// See [Script::check_nesting_depth].
+    NestingDepth(usize),
+}
+
+impl Check {
+    /// The checks run by [Script::parse_and_check].
+    pub const DEFAULT: &'static [Check] = &[
+        Check::GotoTargets,
+        Check::DuplicateChoices,
+        Check::ChoiceBodies,
+    ];
+}
+
+/// A speaker whose name is written with more than one casing, see
+/// [Script::inconsistent_speaker_casings].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct InconsistentCasing<'a> {
+    /// The casing used most often, or the one used first when there's a tie.
+    pub suggested: &'a str,
+    /// Every casing of the name, in the order they're first used.
+    pub casings: Vec<&'a str>,
+}
+
 impl fmt::Display for Script {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for el in &self.0 {
@@ -35,8 +93,420 @@ impl Script {
     }
 
     /// Parse a `Script` from a string.
-    pub fn parse(script_str: &str) -> Result<Self, anyhow::Error> {
-        let mut pairs = Parser::parse(Rule::Script, script_str)?;
+    ///
+    /// `|INCLUDE|` commands aren't supported by this method, use [Script::parse_with_includes] instead.
+    pub fn parse(script_str: &str) -> Result<Self, ScriptError> {
+        let script = Self::parse_allowing_includes(script_str)?;
+        if include::contains_include(&script.0) {
+            return Err(ScriptError::UnsupportedCommand(
+                command::INCLUDE_COMMAND.to_owned(),
+            ));
+        }
+
+        Ok(script)
+    }
+
+    /// Parse a `Script` from a string and run the [default checks](Check::DEFAULT) on it,
+    /// returning it only if it's valid.
+    pub fn parse_and_check(script_str: &str) -> anyhow::Result<Self> {
+        Self::parse_and_check_with(script_str, Check::DEFAULT)
+    }
+
+    /// Parse a `Script` from a string and run `checks` on it, in order, returning it only if it
+    /// passes all of them.
+    pub fn parse_and_check_with(script_str: &str, checks: &[Check]) -> anyhow::Result<Self> {
+        let script = Self::parse(script_str).context("Failed to parse script")?;
+        for check in checks {
+            let result = match *check {
+                Check::GotoTargets => script.check_goto_targets(),
+                Check::DuplicateChoices => script.check_duplicate_choices(),
+                Check::ChoiceBodies => script.check_choice_bodies(),
+                Check::NestingDepth(max) => script.check_nesting_depth(max),
+            };
+            result.context("Invalid script")?;
+        }
+
+        Ok(script)
+    }
+
+    /// Read and parse the `Script` in the file at `path`.
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let script_str = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read script {}", path.display()))?;
+
+        Self::parse(&script_str)
+            .with_context(|| format!("Failed to parse script {}", path.display()))
+    }
+
+    /// Read and parse a `Script` from `reader`.
+    pub fn from_reader(mut reader: impl Read) -> anyhow::Result<Self> {
+        let mut script_str = String::new();
+        reader
+            .read_to_string(&mut script_str)
+            .context("Failed to read script")?;
+
+        Self::parse(&script_str).context("Failed to parse script")
+    }
+
+    /// Parse a `Script` from the file at `path`, inlining the body of any scripts referenced by
+    /// `|INCLUDE|` commands.
+    ///
+    /// `resolver` is called to load the contents of each file, starting with `path`. The paths of
+    /// included scripts are relative to the script that includes them. The `%START%` and `%END%`
+    /// markers of an included script are dropped, and the rest of its body is spliced in at the
+    /// include site. Include cycles are reported as errors.
+    pub fn parse_with_includes<P, F>(path: P, mut resolver: F) -> Result<Self, anyhow::Error>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&Path) -> Result<String, anyhow::Error>,
+    {
+        include::load(path.as_ref(), &mut resolver, &mut Vec::new()).map(Self)
+    }
+
+    /// Iterate over every [Command] in this `Script`, at any depth, in the order they're written.
+    pub fn commands(&self) -> impl Iterator<Item = &Command> {
+        let mut commands = Vec::new();
+        visit_non_blocks(&self.0, &mut |el| {
+            if let TopLevelElement::Line(Line::Command(command)) = el {
+                commands.push(command);
+            }
+        });
+
+        commands.into_iter()
+    }
+
+    /// Iterate over every [Marker] in this `Script`, at any depth, in the order they're written.
+    pub fn markers(&self) -> impl Iterator<Item = &Marker> {
+        let mut markers = Vec::new();
+        visit_non_blocks(&self.0, &mut |el| {
+            if let TopLevelElement::Line(Line::Marker(marker)) = el {
+                markers.push(marker);
+            }
+        });
+
+        markers.into_iter()
+    }
+
+    /// Compute the element-level changes needed to turn this `Script` into `other`.
+    ///
+    /// Blocks are flattened before comparing, so reindenting a line doesn't count as a change.
+    pub fn diff(&self, other: &Script) -> Vec<ElementChange> {
+        diff::diff(&self.0, &other.0)
+    }
+
+    /// Count the lines in this `Script`, at any depth. Comments aren't counted.
+    pub fn line_count(&self) -> usize {
+        let mut count = 0;
+        visit_non_blocks(&self.0, &mut |el| {
+            if let TopLevelElement::Line(_) = el {
+                count += 1;
+            }
+        });
+
+        count
+    }
+
+    /// Count the commands in this `Script`, at any depth, keyed by their
+    /// [canonical name](command::Command::canonical_name).
+    pub fn command_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        visit_non_blocks(&self.0, &mut |el| {
+            if let TopLevelElement::Line(Line::Command(command)) = el {
+                *counts
+                    .entry(command.canonical_name().into_owned())
+                    .or_default() += 1;
+            }
+        });
+
+        counts
+    }
+
+    /// Count the markers in this `Script`, at any depth.
+    pub fn marker_count(&self) -> usize {
+        let mut count = 0;
+        visit_non_blocks(&self.0, &mut |el| {
+            if let TopLevelElement::Line(Line::Marker(_)) = el {
+                count += 1;
+            }
+        });
+
+        count
+    }
+
+    /// Get the [ChoiceGroup]s in this `Script`, at any depth.
+    pub fn choice_groups(&self) -> Vec<ChoiceGroup> {
+        ChoiceGroup::collect(&self.0)
+    }
+
+    /// Count the comments in this `Script`, at any depth.
+    pub fn comment_count(&self) -> usize {
+        let mut count = 0;
+        visit_non_blocks(&self.0, &mut |el| {
+            if let TopLevelElement::Comment(_) = el {
+                count += 1;
+            }
+        });
+
+        count
+    }
+
+    /// Format this `Script` like [ToString::to_string], but pad the speakers of the `SAY` commands
+    /// in each block so that their `|SAY|` delimiters line up.
+    ///
+    /// The padding is trimmed from prefixes when parsing, so the output parses back to an equal
+    /// `Script`.
+    pub fn to_pretty_string(&self) -> String {
+        Pretty {
+            elements: &self.0,
+            depth: 0,
+        }
+        .to_string()
+    }
+
+    /// Export this `Script` as a [Mermaid](https://mermaid.js.org/) flowchart.
+    ///
+    /// Each marker and command becomes a node, and edges follow the flow of dialogue. `GOTO`s draw
+    /// dashed edges to the markers they target. Long labels are truncated with an ellipsis.
+    pub fn to_mermaid(&self) -> String {
+        mermaid::to_mermaid(&self.0)
+    }
+
+    /// Rename the marker `from` to `to`, updating every `GOTO` that targets it.
+    ///
+    /// Returns the number of `GOTO`s that were updated. The `%START%` and `%END%` markers can't be
+    /// renamed, and `to` must be a valid marker name that isn't already declared.
+    pub fn rename_marker(&mut self, from: &str, to: &str) -> anyhow::Result<usize> {
+        if from == START_MARKER || from == END_MARKER {
+            bail!("The %{from}% marker can't be renamed");
+        }
+        if !is_valid_marker_name(to) {
+            bail!("{to} isn't a valid marker name, marker names must be ALL-CAPS-KEBAB-CASE");
+        }
+
+        let mut from_is_declared = false;
+        let mut to_is_declared = false;
+        visit_non_blocks(&self.0, &mut |el| {
+            if let TopLevelElement::Line(Line::Marker(marker)) = el {
+                from_is_declared |= marker.name() == from;
+                to_is_declared |= marker.name() == to;
+            }
+        });
+        if !from_is_declared {
+            bail!("Marker %{from}% isn't declared");
+        }
+        if to_is_declared {
+            bail!("Marker %{to}% is already declared");
+        }
+
+        let mut updated = 0;
+        visit_non_blocks_mut(&mut self.0, &mut |el| match el {
+            TopLevelElement::Line(Line::Marker(marker)) if marker.name() == from => {
+                *marker = Marker::new(to.to_owned());
+            }
+            TopLevelElement::Line(Line::Command(command))
+                if command.goto_target() == Some(from) || command.goto_fallback() == Some(from) =>
+            {
+                let rename = |target: &str| if target == from { to } else { target }.to_owned();
+                let target = command.goto_target().map(rename).unwrap_or_default();
+                let fallback = command.goto_fallback().map(rename);
+                *command = command.with_goto_targets(&target, fallback.as_deref());
+                updated += 1;
+            }
+            _ => {}
+        });
+
+        Ok(updated)
+    }
+
+    /// Check that every `GOTO` in this `Script` targets a declared marker. A `GOTO` with a fallback
+    /// only needs one of its two markers declared. Markers can be declared before or after the
+    /// `GOTO`s that target them, and `%START%` and `%END%` are always declared.
+    pub fn check_goto_targets(&self) -> anyhow::Result<()> {
+        let mut declared = BTreeSet::from([START_MARKER, END_MARKER]);
+        visit_non_blocks(&self.0, &mut |el| {
+            if let TopLevelElement::Line(Line::Marker(marker)) = el {
+                declared.insert(marker.name());
+            }
+        });
+
+        let mut undefined = None;
+        visit_non_blocks(&self.0, &mut |el| {
+            if let TopLevelElement::Line(Line::Command(command)) = el {
+                let fallback = command.goto_fallback();
+                match command.goto_target() {
+                    Some(target)
+                        if undefined.is_none()
+                            && !declared.contains(target)
+                            && !fallback.is_some_and(|fallback| declared.contains(fallback)) =>
+                    {
+                        undefined = Some((target, fallback));
+                    }
+                    _ => {}
+                }
+            }
+        });
+        match undefined {
+            Some((target, Some(fallback))) => {
+                bail!("GOTO targets undefined markers %{target}% and fallback %{fallback}%")
+            }
+            Some((target, None)) => bail!("GOTO targets undefined marker %{target}%"),
+            None => {}
+        }
+
+        Ok(())
+    }
+
+    /// Check that no [ChoiceGroup] in this `Script` offers the same choice twice. Choices are the
+    /// same when their suffixes are, so the same text with different conditions is allowed.
+    pub fn check_duplicate_choices(&self) -> anyhow::Result<()> {
+        for group in self.choice_groups() {
+            // The line each choice first appears on.
+            let mut seen = BTreeMap::new();
+            for choice in group.choices() {
+                let command = choice.command();
+                let suffix = command.suffix().unwrap_or_default();
+                match seen.get(suffix) {
+                    Some(&first_line) => {
+                        let lines = match (first_line, command.line()) {
+                            (Some(first), Some(second)) => {
+                                format!(" on lines {first} and {second}")
+                            }
+                            _ => String::new(),
+                        };
+                        bail!("Choice {suffix}{lines} appears more than once in the same choice group");
+                    }
+                    None => {
+                        seen.insert(suffix, command.line());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that every choice in this `Script` is followed by a block with the dialogue that
+    /// results from it. Scripts where some choices intentionally fall through to the next line can
+    /// leave [Check::ChoiceBodies] out of [Script::parse_and_check_with].
+    pub fn check_choice_bodies(&self) -> anyhow::Result<()> {
+        for group in self.choice_groups() {
+            if let Some(choice) = group
+                .choices()
+                .iter()
+                .find(|choice| choice.body().is_none())
+            {
+                let command = choice.command();
+                let suffix = command.suffix().unwrap_or_default();
+                bail!("Choice {suffix}{} has no block after it", on_line(command));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that no block in this `Script` is nested more than `max` levels deep. Blocks directly
+    /// in the script are one level deep.
+    pub fn check_nesting_depth(&self, max: usize) -> anyhow::Result<()> {
+        fn check(elements: &[TopLevelElement], depth: usize, max: usize) -> anyhow::Result<()> {
+            for el in elements {
+                if let TopLevelElement::Block(block) = el {
+                    if depth + 1 > max {
+                        // Blocks are located by their first command, since nothing else has a line.
+                        let mut first_command = None;
+                        visit_non_blocks(block.elements(), &mut |el| {
+                            if let TopLevelElement::Line(Line::Command(command)) = el {
+                                first_command.get_or_insert(command);
+                            }
+                        });
+                        let line = first_command.map(on_line).unwrap_or_default();
+                        bail!(
+                            "Block{line} is at nesting depth {}, deeper than the maximum of {max}",
+                            depth + 1
+                        );
+                    }
+                    check(block.elements(), depth + 1, max)?;
+                }
+            }
+
+            Ok(())
+        }
+
+        check(&self.0, 0, max)
+    }
+
+    /// Get the names of the markers in this `Script` that no `GOTO` targets, with the lines they're
+    /// declared on, in the order they're declared. `%START%` and `%END%` are never reported.
+    pub fn unused_markers(&self) -> Vec<(&str, Option<usize>)> {
+        let mut targets = BTreeSet::new();
+        visit_non_blocks(&self.0, &mut |el| {
+            if let TopLevelElement::Line(Line::Command(command)) = el {
+                targets.extend(command.goto_target());
+                targets.extend(command.goto_fallback());
+            }
+        });
+
+        let mut unused = Vec::new();
+        visit_non_blocks(&self.0, &mut |el| {
+            if let TopLevelElement::Line(Line::Marker(marker)) = el {
+                let name = marker.name();
+                if name != START_MARKER && name != END_MARKER && !targets.contains(name) {
+                    unused.push((name, marker.line()));
+                }
+            }
+        });
+
+        unused
+    }
+
+    /// Find the `SAY` speakers in this `Script` whose names are written with more than one casing,
+    /// like `ZELDA` and `Zelda`, in the order they first speak. Each is reported with the casing
+    /// to use everywhere instead.
+    pub fn inconsistent_speaker_casings(&self) -> Vec<InconsistentCasing<'_>> {
+        // Each speaker's casings, with how often each is used.
+        let mut speakers: Vec<(String, Vec<(&str, usize)>)> = Vec::new();
+        for speaker in self.commands().flat_map(Command::speakers) {
+            let key = speaker.to_lowercase();
+            let casings = match speakers.iter_mut().find(|(other, _)| *other == key) {
+                Some((_, casings)) => casings,
+                None => {
+                    speakers.push((key, Vec::new()));
+                    &mut speakers.last_mut().expect("a speaker was just added").1
+                }
+            };
+
+            match casings.iter_mut().find(|(casing, _)| *casing == speaker) {
+                Some((_, count)) => *count += 1,
+                None => casings.push((speaker, 1)),
+            }
+        }
+
+        speakers
+            .into_iter()
+            .filter(|(_, casings)| casings.len() > 1)
+            .map(|(_, casings)| {
+                let most_used = casings.iter().map(|(_, count)| *count).max();
+                let (suggested, _) = casings
+                    .iter()
+                    .find(|(_, count)| Some(*count) == most_used)
+                    .expect("there are several casings");
+
+                InconsistentCasing {
+                    suggested,
+                    casings: casings.iter().map(|(casing, _)| *casing).collect(),
+                }
+            })
+            .collect()
+    }
+
+    pub(crate) fn parse_allowing_includes(script_str: &str) -> Result<Self, ScriptError> {
+        if script_str.trim().is_empty() {
+            return Err(ScriptError::EmptyInput("Script"));
+        }
+
+        block::check_indentation(script_str)?;
+
+        let mut pairs = Parser::parse(Rule::Script, script_str).map_err(ScriptError::from_pest)?;
         let pair = pairs.next().expect("a pair exists");
         assert_eq!(pairs.next(), None);
 
@@ -44,19 +514,136 @@ impl Script {
     }
 }
 
+/// Formats elements with their `SAY` speakers aligned, see [Script::to_pretty_string].
+struct Pretty<'a> {
+    elements: &'a [TopLevelElement],
+    depth: usize,
+}
+
+impl fmt::Display for Pretty<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let speaker_width = self
+            .elements
+            .iter()
+            .filter_map(|el| match el {
+                TopLevelElement::Line(Line::Command(command)) if command.is_say() => {
+                    command.prefix()
+                }
+                _ => None,
+            })
+            .map(|speaker| speaker.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        for el in self.elements {
+            if let TopLevelElement::Block(block) = el {
+                let inner = Pretty {
+                    elements: block.elements(),
+                    depth: self.depth + 1,
+                };
+                write!(f, "{inner}")?;
+                continue;
+            }
+
+            for _ in 0..self.depth {
+                write!(f, "{}", block::INDENT)?;
+            }
+            match el {
+                TopLevelElement::Line(Line::Command(command)) if command.is_say() => {
+                    command.fmt_with_prefix_width(f, speaker_width)?;
+                    writeln!(f)?;
+                }
+                el => write!(f, "{el}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse every script in `paths` and run the [default checks](Check::DEFAULT) on it, returning each
+/// path alongside the result of checking it.
+///
+/// Results are returned in the same order as `paths`. When the `parallel` feature is enabled, the
+/// scripts are checked across multiple threads.
+pub fn check_scripts(paths: &[PathBuf]) -> Vec<(PathBuf, Result<(), anyhow::Error>)> {
+    fn check(path: &PathBuf) -> (PathBuf, Result<(), anyhow::Error>) {
+        let result = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read script {}", path.display()))
+            .and_then(|script_str| {
+                Script::parse_and_check_with(&script_str, Check::DEFAULT)
+                    .with_context(|| format!("Failed to check script {}", path.display()))
+            })
+            .map(|_| ());
+
+        (path.clone(), result)
+    }
+
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+        paths.par_iter().map(check).collect()
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        paths.iter().map(check).collect()
+    }
+}
+
+/// Describe where `command` is for an error message, if it was parsed from a script.
+fn on_line(command: &Command) -> String {
+    command
+        .line()
+        .map(|line| format!(" on line {line}"))
+        .unwrap_or_default()
+}
+
+/// Call `f` with every element that isn't a block, descending into blocks depth-first.
+fn visit_non_blocks<'a>(elements: &'a [TopLevelElement], f: &mut impl FnMut(&'a TopLevelElement)) {
+    for el in elements {
+        match el {
+            TopLevelElement::Block(block) => visit_non_blocks(block.elements(), f),
+            el => f(el),
+        }
+    }
+}
+
+/// Like [visit_non_blocks], but with mutable access to each element.
+fn visit_non_blocks_mut(
+    elements: &mut [TopLevelElement],
+    f: &mut impl FnMut(&mut TopLevelElement),
+) {
+    for el in elements {
+        match el {
+            TopLevelElement::Block(block) => visit_non_blocks_mut(block.elements_mut(), f),
+            el => f(el),
+        }
+    }
+}
+
+impl FromStr for Script {
+    type Err = ScriptError;
+
+    fn from_str(script_str: &str) -> Result<Self, Self::Err> {
+        Self::parse(script_str)
+    }
+}
+
 impl TryFrom<Pair<'_, Rule>> for Script {
-    type Error = anyhow::Error;
+    type Error = ScriptError;
 
     fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
         match pair.as_rule() {
             Rule::Script => {
                 let inner = pair
                     .into_inner()
+                    .filter(|pair| pair.as_rule() != Rule::EOI)
                     .map(|pair| match pair.as_rule() {
-                        Rule::Block => Block::parse(pair.as_str()).map(Into::into),
-                        Rule::Line => Line::parse(pair.as_str()).map(Into::into),
+                        Rule::Block => Block::try_from(pair).map(Into::into),
+                        Rule::Line => Line::try_from(pair).map(Into::into),
+                        Rule::Comment => Comment::try_from(pair).map(Into::into),
                         _ => unreachable!(
-                        "Scripts can't contain anything other than blocks or lines but found {:?}",
+                        "Scripts can't contain anything other than blocks, lines, or comments but found {:?}",
                         pair.as_rule()
                     ),
                     })
@@ -64,15 +651,21 @@ impl TryFrom<Pair<'_, Rule>> for Script {
 
                 Ok(Self(inner))
             }
-            _ => bail!("Pair is not a script: {:#?}", pair),
+            rule => Err(ScriptError::unexpected_rule(Rule::Script, rule)),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Script;
+    use super::{Check, InconsistentCasing, Script};
+    use crate::script::element::TopLevelElement;
+    use crate::script::error::ScriptError;
+    use crate::script::{
+        block::Block, command::Command, comment::Comment, line::Line, marker::Marker,
+    };
     use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
 
     #[test]
     fn test_complex_is_nesting_parsed_correctly() {
@@ -121,4 +714,685 @@ mod tests {
         // Empty lines are swallowed by the parser, so our test scripts don't have any.
         assert_eq!(input, script.to_string());
     }
+
+    #[test]
+    fn test_choices_without_bodies_round_trip() {
+        let input = "%START%
+ZELDA |SAY| \"Which way?\"
+    |CHOICE| Left
+    |CHOICE| Right
+    |CHOICE| Back
+ZELDA |SAY| \"Let's go!\"
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(input, script.to_string());
+    }
+
+    #[test]
+    fn test_top_level_choices_without_bodies_round_trip() {
+        let input = "%START%
+|CHOICE| Left
+|CHOICE| Right
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(input, script.to_string());
+    }
+
+    #[test]
+    fn test_block_right_after_start_round_trips() {
+        let input = "%START%
+    |SAY| Already indented
+|SAY| Back at the top level
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert!(matches!(script.0[1], TopLevelElement::Block(_)));
+        assert_eq!(input, script.to_string());
+    }
+
+    #[test]
+    fn test_disabled_command_in_block_round_trips() {
+        let input = "%START%
+ZELDA |SAY| \"Hey there!\"
+    |CHOICE| \"Hello!\"
+        // YOU |SAY| \"Hello!\"
+        ZELDA |SAY| \"How are you?\"
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(1, script.comment_count());
+        assert_eq!(input, script.to_string());
+    }
+
+    #[test]
+    fn test_bare_trailing_slashes_in_script() {
+        let input = "%START%\n|SAY| a //\n    |SAY| b //\n%END%\n";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(
+            vec![Some("a //"), Some("b //")],
+            script.commands().map(Command::suffix).collect::<Vec<_>>()
+        );
+        assert_eq!(input, script.to_string());
+    }
+
+    #[test]
+    fn test_disabled_commands_are_comments() {
+        let input = "%START%
+// ZELDA |SAY| \"Hi\"
+ZELDA |SAY| \"Hey there!\"
+    |SAY| \"First\"
+    // |GOTO| %START%
+    |SAY| \"Second\"
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(2, script.comment_count());
+        assert_eq!(3, script.commands().count());
+        assert_eq!(input, script.to_string());
+    }
+
+    #[test]
+    fn test_step_by_step_nesting_is_allowed() {
+        let input = "%START%
+|SAY| 1
+    |SAY| 2
+        |SAY| 3
+|SAY| 4
+%END%
+";
+        assert!(Script::parse(input).is_ok());
+    }
+
+    #[test]
+    fn test_indentation_jump_is_an_error() {
+        let input = "%START%
+|SAY| 1
+    |SAY| 2
+            |SAY| 3
+%END%
+";
+        assert_eq!(
+            ScriptError::UnexpectedIndentation { line: 4 },
+            Script::parse(input).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_triple_quotes_mid_text_dont_hide_indentation() {
+        let input = "%START%
+|SAY| He said \"\"\"hi
+            |SAY| 3
+%END%
+";
+        assert_eq!(
+            ScriptError::UnexpectedIndentation { line: 3 },
+            Script::parse(input).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_triple_quotes_followed_by_text_are_plain_text() {
+        let input = "%START%\n|SAY| \"\"\"hi\"\"\" she said\n%END%\n";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(
+            Some("\"\"\"hi\"\"\" she said"),
+            script.commands().next().unwrap().suffix()
+        );
+        assert_eq!(input, script.to_string());
+    }
+
+    #[test]
+    fn test_pretty_string_aligns_speakers() {
+        let input = std::fs::read_to_string("example_scripts/daisy-and-luigi.script")
+            .expect("example script exists");
+        let script = Script::parse(&input).expect("a script can be parsed");
+
+        let expected = "%START%
+DAISY |SAY| \"This is a test.\"
+DAISY |SAY| \"You got that?\"
+    |CHOICE| \"Come again?\"
+        LUIGI |SAY| \"Come again?\"
+        |GOTO| %START%
+    |CHOICE| \"Ah, yes. Thank you.\"
+        LUIGI |SAY| \"Ah, yes. Thank you.\"
+DAISY |SAY| \"You're welcome.\"
+%END%
+";
+        assert_eq!(expected, script.to_pretty_string());
+
+        let script = Script::parse(
+            "%START%
+ZELDA |SAY| \"Hey there!\"
+STANDARDIZED TEST |SAY| \"Hello.\"
+|GOTO| %END%
+%END%
+",
+        )
+        .expect("a script can be parsed");
+        let pretty = script.to_pretty_string();
+
+        let expected = "%START%
+ZELDA             |SAY| \"Hey there!\"
+STANDARDIZED TEST |SAY| \"Hello.\"
+|GOTO| %END%
+%END%
+";
+        assert_eq!(expected, pretty);
+        let reparsed = Script::parse(&pretty).expect("pretty output can be parsed");
+        assert_eq!(script.0, reparsed.0);
+    }
+
+    #[test]
+    fn test_counts() {
+        let input = std::fs::read_to_string("example_scripts/daisy-and-luigi.script")
+            .expect("example script exists");
+        let script = Script::parse(&input).expect("a script can be parsed");
+
+        let expected = BTreeMap::from([
+            ("CHOICE".to_owned(), 2),
+            ("GOTO".to_owned(), 1),
+            ("SAY".to_owned(), 5),
+        ]);
+        assert_eq!(expected, script.command_counts());
+        assert_eq!(10, script.line_count());
+        assert_eq!(2, script.marker_count());
+        assert_eq!(0, script.comment_count());
+    }
+
+    #[test]
+    fn test_comments_are_counted() {
+        let input = "// The shortest script
+%START%
+// Nothing happens
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(2, script.comment_count());
+        assert_eq!(2, script.line_count());
+        assert_eq!(input, script.to_string());
+    }
+
+    #[test]
+    fn test_rename_marker() {
+        let input = "%START%
+DAISY |SAY| \"You got that?\"
+    |CHOICE| \"Come again?\"
+        |GOTO| %QUESTION%
+    |CHOICE| \"Skip ahead.\"
+        |GOTO| %QUESTION% IF asked == true
+%QUESTION%
+DAISY |SAY| \"What's your question?\"
+%END%
+";
+        let mut script = Script::parse(input).expect("a script can be parsed");
+
+        let updated = script
+            .rename_marker("QUESTION", "ASK")
+            .expect("marker can be renamed");
+
+        let expected = "%START%
+DAISY |SAY| \"You got that?\"
+    |CHOICE| \"Come again?\"
+        |GOTO| %ASK%
+    |CHOICE| \"Skip ahead.\"
+        |GOTO| %ASK% IF asked == true
+%ASK%
+DAISY |SAY| \"What's your question?\"
+%END%
+";
+        assert_eq!(2, updated);
+        assert_eq!(expected, script.to_string());
+    }
+
+    #[test]
+    fn test_rename_marker_errors() {
+        let input = "%START%\n%MIDDLE%\n%OTHER%\n%END%\n";
+        let mut script = Script::parse(input).expect("a script can be parsed");
+
+        assert!(script.rename_marker("START", "BEGIN").is_err());
+        assert!(script.rename_marker("MIDDLE", "OTHER").is_err());
+        assert!(script.rename_marker("MISSING", "FOUND").is_err());
+        assert!(script.rename_marker("MIDDLE", "lowercase").is_err());
+        assert_eq!(input, script.to_string());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let input = std::fs::read_to_string("example_scripts/daisy-and-luigi.script")
+            .expect("example script exists");
+        let script = Script::parse(&input).expect("a script can be parsed");
+
+        let json = serde_json::to_string(&script).expect("a script can be serialized");
+        let deserialized: Script =
+            serde_json::from_str(&json).expect("a script can be deserialized");
+
+        assert_eq!(script.0, deserialized.0);
+        assert_eq!(input, deserialized.to_string());
+    }
+
+    #[test]
+    fn test_from_str() {
+        fn round_trip<T: std::str::FromStr<Err = ScriptError> + std::fmt::Display>(input: &str) {
+            let parsed: T = input.parse().expect("input is valid");
+            assert_eq!(input, parsed.to_string());
+        }
+
+        round_trip::<Script>("%START%\nZELDA |SAY| \"Hey there!\"\n%END%\n");
+        round_trip::<Block>("    |CHOICE| \"Hello!\"\n");
+        round_trip::<Line>("%START%\n");
+        round_trip::<Command>("ZELDA |SAY| \"Hey there!\"");
+        round_trip::<Marker>("%START%");
+        round_trip::<Comment>("// This is a comment\n");
+
+        assert!("ZELDA SAY Hello".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn test_from_file() {
+        let script = Script::from_file("example_scripts/two-line.script").expect("script is valid");
+        assert_eq!(4, script.line_count());
+
+        let err = Script::from_file("example_scripts/missing.script").unwrap_err();
+        assert_eq!(
+            "Failed to read script example_scripts/missing.script",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let input = "%START%\nZELDA |SAY| \"Hey there!\"\n%END%\n";
+        let script = Script::from_reader(input.as_bytes()).expect("script is valid");
+
+        assert_eq!(input, script.to_string());
+        assert!(Script::from_reader("%START%\n|SAY|\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_commands_and_markers() {
+        let input = std::fs::read_to_string("example_scripts/daisy-and-luigi.script")
+            .expect("example script exists");
+        let script = Script::parse(&input).expect("a script can be parsed");
+
+        let luigi_lines: Vec<_> = script
+            .commands()
+            .filter(|command| command.prefix() == Some("LUIGI"))
+            .filter_map(|command| command.suffix())
+            .collect();
+        assert_eq!(
+            vec!["\"Come again?\"", "\"Ah, yes. Thank you.\""],
+            luigi_lines
+        );
+        assert_eq!(8, script.commands().count());
+
+        let markers: Vec<_> = script.markers().map(|marker| marker.name()).collect();
+        assert_eq!(vec!["START", "END"], markers);
+    }
+
+    #[test]
+    fn test_triple_quoted_say_in_block_round_trips() {
+        let input = "%START%
+ZELDA |SAY| \"Tell me a story.\"
+    |CHOICE| \"Alright.\"
+        YOU |SAY| \"\"\"
+  It was a dark and stormy night.
+// The rain fell in torrents.
+        \"\"\"
+        ZELDA |SAY| \"Spooky!\"
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(4, script.commands().count());
+        assert_eq!(0, script.comment_count());
+        assert_eq!(input, script.to_string());
+    }
+
+    #[test]
+    fn test_tab_indentation() {
+        let with_spaces = std::fs::read_to_string("example_scripts/capital-of-spain.script")
+            .expect("example script exists");
+        let with_tabs = with_spaces.replace("    ", "\t");
+
+        let script = Script::parse(&with_tabs).expect("a script can be parsed");
+
+        assert_eq!(Script::parse(&with_spaces).unwrap().0, script.0);
+        assert_eq!(with_spaces, script.to_string());
+    }
+
+    #[test]
+    fn test_mixed_indentation_is_an_error() {
+        let input = "%START%
+|SAY| Hi
+\t|CHOICE| A
+        |SAY| A
+%END%
+";
+        assert_eq!(
+            ScriptError::MixedIndentation { line: 4 },
+            Script::parse(input).unwrap_err()
+        );
+
+        let input = "%START%\n|SAY| Hi\n\t    |CHOICE| A\n%END%\n";
+        assert_eq!(
+            ScriptError::MixedIndentation { line: 3 },
+            Script::parse(input).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_triple_quotes_in_comments_dont_hide_indentation() {
+        let input = "%START%
+// use \"\"\" for long text
+|SAY| Hi // or \"\"\"
+\t|CHOICE| A
+        |SAY| A
+%END%
+";
+        assert_eq!(
+            ScriptError::MixedIndentation { line: 5 },
+            Script::parse(input).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_comment_without_final_newline() {
+        let script = Script::parse("%START%\n%END%\n// bye").expect("a script can be parsed");
+
+        assert_eq!(1, script.comment_count());
+        assert_eq!("%START%\n%END%\n// bye\n", script.to_string());
+    }
+
+    #[test]
+    fn test_crlf_line_endings() {
+        let lf = std::fs::read_to_string("example_scripts/two-line.script")
+            .expect("example script exists");
+        let crlf = lf.replace('\n', "\r\n");
+
+        let script = Script::parse(&crlf).expect("a script can be parsed");
+
+        assert_eq!(Script::parse(&lf).unwrap().0, script.0);
+        assert_eq!(lf, script.to_string());
+    }
+
+    #[test]
+    fn test_crlf_in_blocks_and_comments() {
+        let lf = "%START%
+// A comment
+ZELDA |SAY| \"Hey there!\"
+    |CHOICE| \"Hello!\"
+        // Another comment
+        YOU |SAY| \"\"\"
+Hello!
+\"\"\"
+%END%
+";
+        let crlf = lf.replace('\n', "\r\n");
+
+        let script = Script::parse(&crlf).expect("a script can be parsed");
+
+        assert_eq!(lf, script.to_string());
+    }
+
+    #[test]
+    fn test_trailing_comments_round_trip() {
+        let input = "%START%
+ZELDA |SAY| \"Hey there!\" // first greeting
+    |CHOICE| \"Hello!\" // the polite option
+        YOU |SAY| \"Hello!\"
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(0, script.comment_count());
+        assert_eq!(input, script.to_string());
+    }
+
+    #[test]
+    fn test_check_goto_targets() {
+        let input = "%START%
+|GOTO| %LATER% IF flag == 1
+%LATER%
+|GOTO| %START%
+|GOTO| %END%
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+        assert!(script.check_goto_targets().is_ok());
+
+        let input = "%START%\n    |GOTO| %FOO%\n%END%\n";
+        let script = Script::parse(input).expect("a script can be parsed");
+        assert_eq!(
+            "GOTO targets undefined marker %FOO%",
+            script.check_goto_targets().unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_check_duplicate_choices() {
+        let input = "%START%
+|CHOICE| \"Yes\"
+    |CHOICE| \"Yes\"
+|CHOICE| \"No\"
+|CHOICE| \"No\" IF brave == true
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+        assert!(script.check_duplicate_choices().is_ok());
+
+        let input = "%START%
+|SAY| Ready?
+    |CHOICE| \"Yes\"
+    |CHOICE| \"No\"
+    |CHOICE| \"Yes\"
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+        assert_eq!(
+            "Choice \"Yes\" on lines 3 and 5 appears more than once in the same choice group",
+            script.check_duplicate_choices().unwrap_err().to_string()
+        );
+        assert_eq!(
+            "Invalid script: Choice \"Yes\" on lines 3 and 5 appears more than once in the same choice group",
+            format!("{:#}", Script::parse_and_check(input).unwrap_err())
+        );
+    }
+
+    #[test]
+    fn test_check_choice_bodies() {
+        let input = std::fs::read_to_string("example_scripts/daisy-and-luigi.script")
+            .expect("example script exists");
+        let script = Script::parse(&input).expect("a script can be parsed");
+        assert!(script.check_choice_bodies().is_ok());
+
+        let input = "%START%\n|CHOICE| \"Fight\"\n|CHOICE| \"Flee\"\n    |GOTO| %END%\n%END%\n";
+        let script = Script::parse(input).expect("a script can be parsed");
+        assert_eq!(
+            "Choice \"Fight\" on line 2 has no block after it",
+            script.check_choice_bodies().unwrap_err().to_string()
+        );
+        assert_eq!(
+            "Invalid script: Choice \"Fight\" on line 2 has no block after it",
+            format!("{:#}", Script::parse_and_check(input).unwrap_err())
+        );
+    }
+
+    #[test]
+    fn test_parse_and_check() {
+        let script =
+            Script::parse_and_check("%START%\n|GOTO| %END%\n%END%\n").expect("script is valid");
+        assert_eq!(1, script.commands().count());
+
+        let err = Script::parse_and_check("%START%\n|GOTO| %FOO%\n%END%\n").unwrap_err();
+        assert_eq!(
+            "Invalid script: GOTO targets undefined marker %FOO%",
+            format!("{err:#}")
+        );
+
+        let err = Script::parse_and_check("%START%\n|SAY|\n%END%\n").unwrap_err();
+        assert!(err.downcast_ref::<ScriptError>().is_some());
+    }
+
+    #[test]
+    fn test_parse_and_check_with() {
+        let input = "%START%\n|CHOICE| \"Fight\"\n|CHOICE| \"Flee\"\n    |GOTO| %END%\n%END%\n";
+        assert!(Script::parse_and_check(input).is_err());
+        assert!(Script::parse_and_check_with(
+            input,
+            &[Check::GotoTargets, Check::DuplicateChoices]
+        )
+        .is_ok());
+
+        let err = Script::parse_and_check_with(input, &[Check::NestingDepth(0)]).unwrap_err();
+        assert_eq!(
+            "Invalid script: Block on line 4 is at nesting depth 1, deeper than the maximum of 0",
+            format!("{err:#}")
+        );
+    }
+
+    #[test]
+    fn test_check_goto_targets_with_fallback() {
+        let input = "%START%\n|GOTO| %REMOVED% %START%\n%END%\n";
+        let script = Script::parse(input).expect("a script can be parsed");
+        assert!(script.check_goto_targets().is_ok());
+
+        let input = "%START%\n|GOTO| %FOO% %BAR%\n%END%\n";
+        let script = Script::parse(input).expect("a script can be parsed");
+        assert_eq!(
+            "GOTO targets undefined markers %FOO% and fallback %BAR%",
+            script.check_goto_targets().unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_rename_marker_keeps_trailing_comment() {
+        let input = "%START%\n%OLD%\n|GOTO| %OLD% // loop back\n%END%\n";
+        let mut script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(1, script.rename_marker("OLD", "NEW").unwrap());
+        assert_eq!(
+            "%START%\n%NEW%\n|GOTO| %NEW% // loop back\n%END%\n",
+            script.to_string()
+        );
+    }
+
+    #[test]
+    fn test_rename_fallback_marker() {
+        let input = "%START%\n|GOTO| %GONE% %OLD% IF flag == 1\n%OLD%\n%END%\n";
+        let mut script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(1, script.rename_marker("OLD", "NEW").unwrap());
+        assert_eq!(
+            "%START%\n|GOTO| %GONE% %NEW% IF flag == 1\n%NEW%\n%END%\n",
+            script.to_string()
+        );
+    }
+
+    #[test]
+    fn test_unused_markers() {
+        let input = "%START%
+%USED%
+    %UNUSED%
+|GOTO| %USED%
+%ALSO-UNUSED%
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(
+            vec![("UNUSED", Some(3)), ("ALSO-UNUSED", Some(5))],
+            script.unused_markers()
+        );
+    }
+
+    #[test]
+    fn test_check_nesting_depth() {
+        let input = "%START%
+|SAY| One
+    |SAY| Two
+        // Deep
+        |SAY| Three
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert!(script.check_nesting_depth(2).is_ok());
+        assert_eq!(
+            "Block on line 5 is at nesting depth 2, deeper than the maximum of 1",
+            script.check_nesting_depth(1).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_consistent_speaker_casing() {
+        let input = std::fs::read_to_string("example_scripts/daisy-and-luigi.script")
+            .expect("example script exists");
+        let script = Script::parse(&input).expect("a script can be parsed");
+
+        assert!(script.inconsistent_speaker_casings().is_empty());
+    }
+
+    #[test]
+    fn test_inconsistent_speaker_casing() {
+        let input = "%START%
+Zelda |SAY| Hi
+ZELDA |SAY| Hey
+LINK |SAY| ...
+ZELDA & Link |SAY| Bye
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(
+            vec![
+                InconsistentCasing {
+                    suggested: "ZELDA",
+                    casings: vec!["Zelda", "ZELDA"],
+                },
+                InconsistentCasing {
+                    suggested: "LINK",
+                    casings: vec!["LINK", "Link"],
+                },
+            ],
+            script.inconsistent_speaker_casings()
+        );
+    }
+
+    #[test]
+    fn test_check_scripts() {
+        let mut paths = std::fs::read_dir("example_scripts")
+            .expect("example scripts exist")
+            .map(|entry| entry.expect("entry can be read").path())
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.push("example_scripts/missing.script".into());
+
+        let results = super::check_scripts(&paths);
+
+        let checked_paths = results.iter().map(|(path, _)| path).collect::<Vec<_>>();
+        assert_eq!(paths.iter().collect::<Vec<_>>(), checked_paths);
+        let (missing, found) = results.split_last().expect("there are results");
+        assert!(found.iter().all(|(_, result)| result.is_ok()));
+        assert!(missing.1.is_err());
+    }
+
+    #[test]
+    fn test_script_without_trailing_newline_parses() {
+        let input = std::fs::read_to_string("example_scripts/three-line.script")
+            .expect("example script exists");
+
+        let script = Script::parse(&input).expect("a script can be parsed");
+        assert_eq!(format!("{input}\n"), script.to_string());
+    }
 }
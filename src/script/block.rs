@@ -1,6 +1,6 @@
 //! # Blocks
 //!
-//! Blocks are used to organize dialogue. They are indented by 4 spaces and can contain any number of lines or inner blocks. Blocks can be nested to any depth, though you should avoid nesting deeply, as it makes scripts difficult to read. The |CHOICE| and |GOTO| commands show examples of how blocks can be used. When a block is entered, dialogue will continue from the first line of the block. When a block is exited, dialogue will continue from the first line after the block.
+//! Blocks are used to organize dialogue. They are indented by 4 spaces or a tab, though a script must stick to one or the other, and can contain any number of lines or inner blocks. Blocks can be nested to any depth, though you should avoid nesting deeply, as it makes scripts difficult to read. The |CHOICE| and |GOTO| commands show examples of how blocks can be used. When a block is entered, dialogue will continue from the first line of the block. When a block is exited, dialogue will continue from the first line after the block.
 //!
 //! ```text
 //! %START%
@@ -27,16 +27,19 @@
 //! Blocks should always be used to organize choices, and commands that result from a choice should be in a block after that choice.
 
 use crate::script::{
+    command::TRIPLE_QUOTE,
+    comment::Comment,
+    error::ScriptError,
     line::Line,
     parser::{Parser, Rule},
     TopLevelElement,
 };
-use anyhow::bail;
 use pest::{iterators::Pair, Parser as PestParser};
-use std::fmt;
+use std::{fmt, str::FromStr};
 
 /// A block in a script, containing a collection of [top level elements](TopLevelElement).
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block {
     inner: Vec<TopLevelElement>,
 }
@@ -63,9 +66,42 @@ impl Block {
         &self.inner
     }
 
+    /// Get the number of direct children of this [Block]. Elements of inner blocks aren't counted,
+    /// but each inner block counts as one child.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Check whether this [Block] has no children.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Count the commands in this [Block], including those in inner blocks.
+    pub fn command_count(&self) -> usize {
+        self.inner
+            .iter()
+            .map(|el| match el {
+                TopLevelElement::Block(block) => block.command_count(),
+                TopLevelElement::Line(Line::Command(_)) => 1,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    pub(crate) fn elements_mut(&mut self) -> &mut Vec<TopLevelElement> {
+        &mut self.inner
+    }
+
     /// Parse a [Block] from a string.
-    pub fn parse(block_str: &str) -> Result<Self, anyhow::Error> {
-        let mut pairs = Parser::parse(Rule::Block, block_str)?;
+    pub fn parse(block_str: &str) -> Result<Self, ScriptError> {
+        if block_str.trim().is_empty() {
+            return Err(ScriptError::EmptyInput("Block"));
+        }
+
+        check_indentation(block_str)?;
+
+        let mut pairs = Parser::parse(Rule::Block, block_str).map_err(ScriptError::from_pest)?;
         let pair = pairs.next().expect("a pair exists");
         assert_eq!(
             pairs.next(),
@@ -83,13 +119,13 @@ impl Block {
                 TopLevelElement::Block(block) => block.fmt_with_indent(f, indent + 1)?,
                 TopLevelElement::Line(line) => {
                     for _ in 0..indent {
-                        write!(f, "    ")?;
+                        write!(f, "{INDENT}")?;
                     }
                     write!(f, "{line}")?;
                 }
                 TopLevelElement::Comment(comment) => {
                     for _ in 0..indent {
-                        write!(f, "    ")?;
+                        write!(f, "{INDENT}")?;
                     }
                     write!(f, "{comment}")?;
                 }
@@ -100,8 +136,88 @@ impl Block {
     }
 }
 
+/// The indentation of a single block level.
+pub(crate) const INDENT: &str = "    ";
+
+/// Check that each line is indented by a whole number of levels, and never more than one level
+/// deeper than the line before it. Without this, the extra indentation of an over-indented line
+/// would be silently swallowed by its prefix. Comments, blank lines, and the continuation lines of
+/// triple-quoted suffixes are ignored.
+///
+/// A level is either four spaces or a tab, and all the lines must use the same one.
+pub(crate) fn check_indentation(input: &str) -> Result<(), ScriptError> {
+    let mut previous_level = 0;
+    let mut indent_char = None;
+    let mut in_triple_quote = false;
+
+    for (index, line) in input.lines().enumerate() {
+        let content = line.trim_start_matches([' ', '\t']);
+        if in_triple_quote {
+            // Anything after the closing quotes can only be a trailing comment.
+            in_triple_quote = !line.contains(TRIPLE_QUOTE);
+            continue;
+        }
+        in_triple_quote = opens_triple_quote(content);
+
+        if content.trim().is_empty() || content.starts_with("//") {
+            continue;
+        }
+
+        let indent = &line[..line.len() - content.len()];
+        let level = match indent.chars().next() {
+            None => 0,
+            Some(c) => {
+                if indent.chars().any(|other| other != c) || indent_char.is_some_and(|i| i != c) {
+                    return Err(ScriptError::MixedIndentation { line: index + 1 });
+                }
+                indent_char = Some(c);
+
+                match c {
+                    '\t' => indent.len(),
+                    _ if indent.len() % INDENT.len() == 0 => indent.len() / INDENT.len(),
+                    _ => return Err(ScriptError::UnexpectedIndentation { line: index + 1 }),
+                }
+            }
+        };
+        if level > previous_level + 1 {
+            return Err(ScriptError::UnexpectedIndentation { line: index + 1 });
+        }
+        previous_level = level;
+    }
+
+    Ok(())
+}
+
+/// Check whether `content`, a line with its indentation removed, opens triple-quoted text without
+/// closing it. Like in the grammar, triple-quoted text has to start right after the command name,
+/// so triple quotes anywhere else, including in comments, are ignored.
+fn opens_triple_quote(content: &str) -> bool {
+    if content.starts_with("//") {
+        return false;
+    }
+
+    // Prefixes can't contain pipes, so the suffix follows the second one.
+    let mut parts = content.splitn(3, '|');
+    let suffix = match (parts.next(), parts.next(), parts.next()) {
+        (Some(_), Some(_), Some(rest)) => rest.strip_prefix(' ').unwrap_or(rest),
+        _ => return false,
+    };
+
+    suffix
+        .strip_prefix(TRIPLE_QUOTE)
+        .is_some_and(|text| !text.contains(TRIPLE_QUOTE))
+}
+
+impl FromStr for Block {
+    type Err = ScriptError;
+
+    fn from_str(block_str: &str) -> Result<Self, Self::Err> {
+        Self::parse(block_str)
+    }
+}
+
 impl TryFrom<Pair<'_, Rule>> for Block {
-    type Error = anyhow::Error;
+    type Error = ScriptError;
 
     fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
         match pair.as_rule() {
@@ -111,15 +227,16 @@ impl TryFrom<Pair<'_, Rule>> for Block {
                     .map(|pair| match pair.as_rule() {
                         Rule::Block => Block::try_from(pair).map(TopLevelElement::Block),
                         Rule::Line => Line::try_from(pair).map(TopLevelElement::Line),
+                        Rule::Comment => Comment::try_from(pair).map(TopLevelElement::Comment),
                         _ => unreachable!(
-                            "Blocks can't contain anything other than inner blocks or lines"
+                            "Blocks can't contain anything other than inner blocks, lines, or comments"
                         ),
                     })
                     .collect::<Result<Vec<_>, _>>()?;
 
                 Ok(Self { inner })
             }
-            _ => bail!("Pair is not a block: {:#?}", pair),
+            rule => Err(ScriptError::unexpected_rule(Rule::Block, rule)),
         }
     }
 }
@@ -127,6 +244,7 @@ impl TryFrom<Pair<'_, Rule>> for Block {
 #[cfg(test)]
 mod tests {
     use super::Block;
+    use crate::script::error::ScriptError;
     use crate::script::{command::Command, line::Line};
     use pretty_assertions::assert_eq;
 
@@ -201,6 +319,33 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn test_len_and_command_count() {
+        let input = "    |SAY| First level
+        |SAY| Second level
+            |SAY| Third level
+        |SAY| Second level again
+    |SAY| First level again
+";
+        let block = Block::parse(input).expect("block is valid");
+
+        assert_eq!(3, block.len());
+        assert!(!block.is_empty());
+        assert_eq!(5, block.command_count());
+        assert!(Block::empty().is_empty());
+        assert_eq!(0, Block::empty().command_count());
+    }
+
+    #[test]
+    fn test_over_indented_block_is_an_error() {
+        let input = "    |SAY| First level
+            |SAY| Third level
+";
+        let err = Block::parse(input).unwrap_err();
+
+        assert_eq!(ScriptError::UnexpectedIndentation { line: 2 }, err);
+    }
+
     #[test]
     fn test_one_line_round_trip() {
         let input = "    |CHOICE| do the thing\n";
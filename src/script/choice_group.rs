@@ -0,0 +1,206 @@
+//! # Choice groups
+//!
+//! A run of sibling `|CHOICE|` lines is presented to the player together, as one decision. Each
+//! choice can be followed by a block with the dialogue that results from it.
+//!
+//! ```text
+//! ZELDA |SAY| "Are you ready?"
+//!     |CHOICE| "Yes."
+//!         ZELDA |SAY| "Let's go then."
+//!     |CHOICE| "No."
+//! ```
+//!
+//! Scripts store choices flat, the way they're written. [ChoiceGroup]s bundle them back together so
+//! that tools don't each need to work out where a group starts and ends. A group ends at the first
+//! element that isn't a choice or the body of one, including comments.
+
+use crate::script::{
+    block::{Block, INDENT},
+    command::Command,
+    element::TopLevelElement,
+    line::Line,
+};
+use std::fmt;
+
+/// A single choice in a [ChoiceGroup].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Choice {
+    command: Command,
+    body: Option<Block>,
+}
+
+impl Choice {
+    /// Get the `CHOICE` [Command] of this [Choice].
+    pub fn command(&self) -> &Command {
+        &self.command
+    }
+
+    /// Get the [Block] of dialogue that follows this [Choice], if it has one.
+    pub fn body(&self) -> Option<&Block> {
+        self.body.as_ref()
+    }
+}
+
+/// A group of contiguous choices, presented to the player together.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ChoiceGroup {
+    choices: Vec<Choice>,
+}
+
+impl fmt::Display for ChoiceGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with_indent(f, 0)
+    }
+}
+
+impl ChoiceGroup {
+    /// Get the choices in this [ChoiceGroup], in the order they're written.
+    pub fn choices(&self) -> &[Choice] {
+        &self.choices
+    }
+
+    /// Find the [ChoiceGroup]s in `elements`, including those in inner blocks. Groups are returned
+    /// in the order they're written, with groups in the body of a choice after the group containing
+    /// that choice.
+    pub fn collect(elements: &[TopLevelElement]) -> Vec<Self> {
+        let mut groups = Vec::new();
+        collect_into(elements, &mut groups);
+
+        groups
+    }
+
+    /// Format this [ChoiceGroup] in its flat form, with the given indentation.
+    pub fn fmt_with_indent(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        for choice in &self.choices {
+            for _ in 0..indent {
+                write!(f, "{INDENT}")?;
+            }
+            writeln!(f, "{}", choice.command)?;
+
+            if let Some(body) = &choice.body {
+                body.fmt_with_indent(f, indent + 1)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn collect_into(elements: &[TopLevelElement], groups: &mut Vec<ChoiceGroup>) {
+    let mut current: Option<ChoiceGroup> = None;
+    let mut nested = Vec::new();
+
+    for el in elements {
+        match el {
+            TopLevelElement::Line(Line::Command(command)) if command.is_choice() => {
+                current
+                    .get_or_insert_with(|| ChoiceGroup {
+                        choices: Vec::new(),
+                    })
+                    .choices
+                    .push(Choice {
+                        command: command.clone(),
+                        body: None,
+                    });
+                continue;
+            }
+            TopLevelElement::Block(block) => {
+                let last_choice = current
+                    .as_mut()
+                    .and_then(|group| group.choices.last_mut())
+                    .filter(|choice| choice.body.is_none());
+
+                if let Some(choice) = last_choice {
+                    choice.body = Some(block.clone());
+                    collect_into(block.elements(), &mut nested);
+                    continue;
+                }
+
+                collect_into(block.elements(), &mut nested);
+            }
+            _ => {}
+        }
+
+        groups.extend(current.take());
+        groups.append(&mut nested);
+    }
+
+    groups.extend(current);
+    groups.append(&mut nested);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChoiceGroup;
+    use crate::script::Script;
+    use pretty_assertions::assert_eq;
+    use std::fmt;
+
+    struct Indented<'a>(&'a ChoiceGroup, usize);
+
+    impl fmt::Display for Indented<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.fmt_with_indent(f, self.1)
+        }
+    }
+
+    #[test]
+    fn test_three_choices_are_grouped() {
+        let choices = "    |CHOICE| \"Yes.\"
+        ZELDA |SAY| \"Let's go then.\"
+    |CHOICE| \"No.\"
+    |CHOICE| \"Maybe?\"
+        ZELDA |SAY| \"Make up your mind.\"
+        |GOTO| %START%
+";
+        let input = format!(
+            "%START%\nZELDA |SAY| \"Are you ready?\"\n{choices}ZELDA |SAY| \"Bye.\"\n%END%\n"
+        );
+        let script = Script::parse(&input).expect("a script can be parsed");
+
+        let groups = ChoiceGroup::collect(&script.0);
+        assert_eq!(1, groups.len());
+
+        let group = &groups[0];
+        let texts: Vec<_> = group
+            .choices()
+            .iter()
+            .map(|choice| choice.command().suffix().unwrap())
+            .collect();
+        assert_eq!(vec!["\"Yes.\"", "\"No.\"", "\"Maybe?\""], texts);
+        assert_eq!(
+            vec![1, 0, 2],
+            group
+                .choices()
+                .iter()
+                .map(|choice| choice.body().map_or(0, |body| body.len()))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(choices, Indented(group, 1).to_string());
+    }
+
+    #[test]
+    fn test_groups_inside_blocks_are_collected() {
+        let input = std::fs::read_to_string("example_scripts/capital-of-spain.script")
+            .expect("example script exists");
+        let script = Script::parse(&input).expect("a script can be parsed");
+
+        let groups = script.choice_groups();
+
+        assert_eq!(1, groups.len());
+        assert_eq!(4, groups[0].choices().len());
+    }
+
+    #[test]
+    fn test_comment_ends_group() {
+        let input = "%START%
+|CHOICE| A
+// Something else
+|CHOICE| B
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert_eq!(2, ChoiceGroup::collect(&script.0).len());
+    }
+}
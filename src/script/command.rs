@@ -3,68 +3,528 @@
 //! Commands are written in ALL-CAPS-KEBAB-CASE and delimited by pipes. Commands can have 'prefixes' and
 //! 'suffixes'. Several built-in commands are supported, and _(in most cases)_ it's easy to extend the language with custom
 //! commands.
+//!
+//! Command names are matched case-insensitively: `|Say|` is treated the same as `|SAY|`. The name is
+//! still stored exactly as written so that scripts round-trip, and [Command::canonical_name] should be
+//! used whenever a command needs to be compared against a built-in.
+//!
+//! Suffixes can contain backslash escapes: `\|` for a pipe, `\n` for a newline, `\\` for a
+//! backslash, and `\/` for a slash. A `//` after whitespace starts a trailing
+//! comment, so it has to be written as `\//` to be part of the suffix. [Command::suffix] returns the text with the escapes resolved,
+//! and they're added back when the command is formatted.
+//!
+//! ```text
+//! ZELDA |SAY| "Either this \| or that."
+//! ```
+//!
+//! Long suffixes can span several lines by wrapping them in triple quotes. Triple-quoted suffixes
+//! are kept exactly as written, quotes included, and escapes aren't resolved in them.
+//!
+//! ```text
+//! |SAY| """
+//! It was a dark and stormy night.
+//! """
+//! ```
 
-use crate::script::parser::{Parser, Rule};
-use anyhow::bail;
+use crate::script::{
+    command_spec::CommandSpec,
+    condition::Condition,
+    error::ScriptError,
+    marker::is_valid_marker_name,
+    parser::{Parser, Rule},
+    text_table::TextTable,
+};
 use pest::iterators::Pair;
 use pest::Parser as PestParser;
 use std::borrow::Cow;
 use std::fmt;
+use std::str::FromStr;
+
+/// The name of the command used to check the game state while testing a script.
+pub const ASSERT_COMMAND: &str = "ASSERT";
+/// The name of the command used to declare a choice the player can make.
+pub const CHOICE_COMMAND: &str = "CHOICE";
+/// The name of the command whose block runs when the condition before it doesn't hold.
+pub const ELSE_COMMAND: &str = "ELSE";
+/// The name of the command used to redirect the flow of dialogue to a marker.
+pub const GOTO_COMMAND: &str = "GOTO";
+/// The name of the command used to display dialogue.
+pub const SAY_COMMAND: &str = "SAY";
+/// The name of the command used to include the body of another script.
+pub const INCLUDE_COMMAND: &str = "INCLUDE";
+/// The name of the command used to set a variable in the game state.
+pub const SET_COMMAND: &str = "SET";
+/// The name of the command used to notify the game that something happened.
+pub const TRIGGER_COMMAND: &str = "TRIGGER";
+/// The name of the command whose block only runs when its condition holds.
+pub const IF_COMMAND: &str = "IF";
+
+/// The keyword separating a command's suffix from its [Condition].
+const CONDITION_KEYWORD: &str = " IF ";
 
 /// A command in a script.
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "SerializedCommand"))]
 pub struct Command {
     name: Cow<'static, str>,
     prefix: Option<Cow<'static, str>>,
     suffix: Option<Cow<'static, str>>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    source: Option<String>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    line: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    condition: Option<Condition>,
+    trailing_comment: Option<String>,
 }
 
-impl fmt::Display for Command {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Some(prefix) = &self.prefix {
-            write!(f, "{prefix} ")?;
-        }
+/// The serialized fields of a [Command]. The condition is derived from the suffix when
+/// deserializing, so that it can't go stale when the suffix is edited.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct SerializedCommand {
+    name: Cow<'static, str>,
+    prefix: Option<Cow<'static, str>>,
+    suffix: Option<Cow<'static, str>>,
+    trailing_comment: Option<String>,
+}
 
-        write!(f, "|{}|", self.name)?;
+#[cfg(feature = "serde")]
+impl TryFrom<SerializedCommand> for Command {
+    type Error = ScriptError;
 
-        if let Some(suffix) = &self.suffix {
-            write!(f, " {suffix}")?;
-        }
+    fn try_from(command: SerializedCommand) -> Result<Self, Self::Error> {
+        let condition = parse_condition(&command.name, command.suffix.as_deref())?;
 
-        Ok(())
+        Ok(Self {
+            condition,
+            trailing_comment: command.trailing_comment,
+            ..Self::new(command.name, command.prefix, command.suffix)
+        })
+    }
+}
+
+/// A typed view of a `SAY` [Command], see [Command::as_say].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Say<'a> {
+    /// The character speaking, if there is one.
+    pub speaker: Option<&'a str>,
+    /// The dialogue to display.
+    pub text: &'a str,
+}
+
+impl PartialEq for Command {
+    fn eq(&self, other: &Self) -> bool {
+        // The source text and line are deliberately ignored, so that commands parsed from
+        // differently formatted input still compare equal. The condition is derived from the suffix.
+        self.name == other.name
+            && self.prefix == other.prefix
+            && self.suffix == other.suffix
+            && self.trailing_comment == other.trailing_comment
+    }
+}
+
+impl Eq for Command {}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with_prefix_width(f, 0)
     }
 }
 
 impl Command {
     /// Create a new [Command].
+    ///
+    /// If the command is a `GOTO` or `ASSERT` with an invalid condition, the condition is ignored. Use
+    /// [Command::parse] to have it reported as an error instead.
     pub fn new<T: Into<Cow<'static, str>>>(name: T, prefix: Option<T>, suffix: Option<T>) -> Self {
+        let name = name.into();
+        let suffix = suffix.map(Into::into);
+        let condition = parse_condition(&name, suffix.as_deref()).unwrap_or_default();
+
         Self {
-            name: name.into(),
+            name,
             prefix: prefix.map(Into::into),
-            suffix: suffix.map(Into::into),
+            suffix,
+            source: None,
+            line: None,
+            condition,
+            trailing_comment: None,
+        }
+    }
+
+    /// Create a new [Command], checking that it follows the rules for its kind.
+    ///
+    /// Built-in commands are checked against their [CommandSpec]. `ASSERT`, `CHOICE`, `ELSE`,
+    /// `GOTO`, and `INCLUDE` commands can't have a prefix. All of them but `ELSE` require a suffix,
+    /// as do `SAY` commands. The condition of an `ASSERT` or `GOTO` must also be valid.
+    pub fn try_new<T: Into<Cow<'static, str>>>(
+        name: T,
+        prefix: Option<T>,
+        suffix: Option<T>,
+    ) -> Result<Self, ScriptError> {
+        let command = Self::new(name, prefix, suffix);
+        let canonical_name = command.canonical_name().into_owned();
+
+        // Blocks aren't known here, and every built-in command allows them.
+        if let Some(spec) = CommandSpec::builtin(&canonical_name) {
+            spec.check(&command, None)?;
+        }
+        if canonical_name == GOTO_COMMAND && !has_valid_goto_targets(command.suffix.as_deref()) {
+            return Err(ScriptError::InvalidCommand {
+                name: canonical_name,
+                reason: "the suffix must be a marker, optionally followed by a fallback marker",
+            });
+        }
+        parse_condition(&command.name, command.suffix.as_deref())?;
+
+        Ok(command)
+    }
+
+    /// Get the name of this [Command], exactly as it was written.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the uppercased name of this [Command]. This is the name used to match built-in commands.
+    pub fn canonical_name(&self) -> Cow<'_, str> {
+        if self.name.chars().any(|c| c.is_ascii_lowercase()) {
+            Cow::Owned(self.name.to_ascii_uppercase())
+        } else {
+            Cow::Borrowed(&self.name)
+        }
+    }
+
+    /// Check whether this is a `SAY` [Command].
+    pub fn is_say(&self) -> bool {
+        self.is(SAY_COMMAND)
+    }
+
+    /// Check whether this is a `CHOICE` [Command].
+    pub fn is_choice(&self) -> bool {
+        self.is(CHOICE_COMMAND)
+    }
+
+    /// Check whether this is a `GOTO` [Command].
+    pub fn is_goto(&self) -> bool {
+        self.is(GOTO_COMMAND)
+    }
+
+    /// Check whether this is a `SET` [Command].
+    pub fn is_set(&self) -> bool {
+        self.is(SET_COMMAND)
+    }
+
+    /// Check whether this is a `TRIGGER` [Command].
+    pub fn is_trigger(&self) -> bool {
+        self.is(TRIGGER_COMMAND)
+    }
+
+    /// Check whether this is an `IF` [Command].
+    pub fn is_if(&self) -> bool {
+        self.is(IF_COMMAND)
+    }
+
+    /// Get the speaker and text of this [Command], if it's a `SAY`.
+    pub fn as_say(&self) -> Option<Say<'_>> {
+        if !self.is_say() {
+            return None;
+        }
+
+        Some(Say {
+            speaker: self.prefix(),
+            text: self.suffix()?,
+        })
+    }
+
+    /// Get the speakers of this [Command], if it's a `SAY`. Speakers that talk in unison are
+    /// separated by ` & ` in the prefix, as in `ZELDA & LINK |SAY| "Surprise!"`.
+    ///
+    /// Returns an empty list for a `SAY` without a speaker, and for every other kind of command.
+    pub fn speakers(&self) -> Vec<&str> {
+        match self.prefix() {
+            Some(prefix) if self.is_say() => prefix
+                .split(SPEAKER_SEPARATOR)
+                .map(str::trim)
+                .filter(|speaker| !speaker.is_empty())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Get the name of the marker this [Command] targets, if it's a `GOTO`. The same as
+    /// [Command::goto_target].
+    pub fn as_goto(&self) -> Option<&str> {
+        self.goto_target()
+    }
+
+    /// Get the text of this [Command] to show the player, if it's a `CHOICE`. The same as
+    /// [Command::choice_text].
+    pub fn as_choice(&self) -> Option<&str> {
+        self.choice_text()
+    }
+
+    fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    /// Get the prefix of this [Command], if it has one.
+    pub fn prefix(&self) -> Option<&str> {
+        self.prefix.as_deref()
+    }
+
+    /// Get the suffix of this [Command], if it has one.
+    pub fn suffix(&self) -> Option<&str> {
+        self.suffix.as_deref()
+    }
+
+    /// Replace the prefix of this [Command].
+    pub fn set_prefix(&mut self, prefix: impl Into<Cow<'static, str>>) {
+        self.prefix = Some(prefix.into());
+        self.source = None;
+    }
+
+    /// Replace the suffix of this [Command].
+    ///
+    /// As with [Command::new], an invalid condition in the new suffix is ignored.
+    pub fn set_suffix(&mut self, suffix: impl Into<Cow<'static, str>>) {
+        let suffix = suffix.into();
+        self.condition = parse_condition(&self.name, Some(&suffix)).unwrap_or_default();
+        self.suffix = Some(suffix);
+        self.source = None;
+    }
+
+    /// Create a copy of this [Command] with a different prefix.
+    pub fn with_prefix(&self, prefix: impl Into<Cow<'static, str>>) -> Self {
+        let mut command = self.clone();
+        command.set_prefix(prefix);
+        command
+    }
+
+    /// Create a copy of this [Command] with a different suffix.
+    pub fn with_suffix(&self, suffix: impl Into<Cow<'static, str>>) -> Self {
+        let mut command = self.clone();
+        command.set_suffix(suffix);
+        command
+    }
+
+    /// Get the text of the comment written after this [Command] on the same line, if it has one.
+    ///
+    /// ```text
+    /// ZELDA |SAY| "Hey there!" // First greeting
+    /// ```
+    pub fn trailing_comment(&self) -> Option<&str> {
+        self.trailing_comment.as_deref()
+    }
+
+    /// Replace the comment written after this [Command] on the same line, or remove it with `None`.
+    pub fn set_trailing_comment(&mut self, comment: Option<String>) {
+        self.trailing_comment = comment;
+        self.source = None;
+    }
+
+    /// Get the suffix of this [Command] without the quotes around it, if it has a suffix.
+    ///
+    /// Only one pair of matching `"` around the whole suffix is removed, so nested quotes aren't
+    /// unwrapped. For triple-quoted suffixes, the triple quotes and the line breaks right inside
+    /// them are removed. Suffixes that aren't quoted are returned unchanged.
+    pub fn unquoted_suffix(&self) -> Option<Cow<'_, str>> {
+        let suffix = self.suffix()?;
+
+        let unquoted = if is_triple_quoted(suffix) {
+            let inner = &suffix[TRIPLE_QUOTE.len()..suffix.len() - TRIPLE_QUOTE.len()];
+            let inner = inner.strip_prefix('\n').unwrap_or(inner);
+            inner.strip_suffix('\n').unwrap_or(inner)
+        } else if suffix.len() >= 2 && suffix.starts_with('"') && suffix.ends_with('"') {
+            &suffix[1..suffix.len() - 1]
+        } else {
+            suffix
+        };
+
+        Some(Cow::Borrowed(unquoted))
+    }
+
+    /// Get the [Condition] attached to this [Command], if it has one.
+    ///
+    /// `GOTO` commands can have a condition written after the marker, like
+    /// `|GOTO| %SHORTCUT% IF flag == 1`, and `CHOICE` commands after their text, like
+    /// `|CHOICE| "Unlock the door" IF has_key == true`. The whole suffix of an `ASSERT` command is
+    /// its condition, like `|ASSERT| gold == 100`.
+    pub fn condition(&self) -> Option<&Condition> {
+        self.condition.as_ref()
+    }
+
+    /// Get the stable key of this [Command], if it's a `CHOICE` with one.
+    ///
+    /// Keys are written in square brackets before the text of the choice, like
+    /// `|CHOICE| [accept] "I accept"`, and stay the same when the text changes.
+    pub fn choice_key(&self) -> Option<&str> {
+        self.split_choice_key()?.0
+    }
+
+    /// Get the text of this [Command] to show the player, if it's a `CHOICE`. Any key or condition is
+    /// left out.
+    pub fn choice_text(&self) -> Option<&str> {
+        self.split_choice_key().map(|(_, text)| text)
+    }
+
+    /// Get the suffix of this [Command] translated for `locale`, looked up in `table` by the
+    /// command's stable key. Falls back to the text embedded in the script when there's no key or
+    /// no translation.
+    pub fn localized_suffix(&self, table: &TextTable, locale: &str) -> Option<String> {
+        let translated = self
+            .choice_key()
+            .and_then(|key| table.get(locale, key))
+            .map(ToOwned::to_owned);
+
+        translated.or_else(|| self.choice_text().or(self.suffix()).map(ToOwned::to_owned))
+    }
+
+    fn split_choice_key(&self) -> Option<(Option<&str>, &str)> {
+        if !self.is_choice() {
+            return None;
+        }
+
+        let mut suffix = self.suffix.as_deref()?;
+        if self.condition.is_some() {
+            if let Some((text, _)) = suffix.rsplit_once(CONDITION_KEYWORD) {
+                suffix = text.trim_end();
+            }
+        }
+
+        let keyed = suffix
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+            .filter(|(key, _)| !key.is_empty() && !key.contains(char::is_whitespace));
+
+        match keyed {
+            Some((key, text)) => Some((Some(key), text.trim_start())),
+            None => Some((None, suffix)),
+        }
+    }
+
+    /// Get the name of the marker targeted by this [Command], if it's a `GOTO`. Any condition and
+    /// fallback marker are left out.
+    pub fn goto_target(&self) -> Option<&str> {
+        goto_targets(self.goto_suffix()?).next()
+    }
+
+    /// Get the name of the marker this [Command] goes to when its target is missing, if it's a
+    /// `GOTO` with a fallback, as in `|GOTO| %PREFERRED% %FALLBACK%`.
+    pub fn goto_fallback(&self) -> Option<&str> {
+        goto_targets(self.goto_suffix()?).nth(1)
+    }
+
+    fn goto_suffix(&self) -> Option<&str> {
+        if !self.is_goto() {
+            return None;
+        }
+
+        let suffix = self.suffix.as_deref()?;
+        match suffix.split_once(CONDITION_KEYWORD) {
+            Some((targets, _)) => Some(targets),
+            None => Some(suffix),
+        }
+    }
+
+    /// Format this [Command], padding its prefix with spaces to at least `width` characters.
+    pub fn fmt_with_prefix_width(&self, f: &mut fmt::Formatter<'_>, width: usize) -> fmt::Result {
+        if let Some(prefix) = &self.prefix {
+            write!(f, "{prefix:<width$} ")?;
+        }
+
+        write!(f, "|{}|", self.name)?;
+
+        match &self.suffix {
+            Some(suffix) if is_triple_quoted(suffix) => write!(f, " {suffix}")?,
+            Some(suffix) => write!(f, " {}", escape(suffix))?,
+            None => {}
+        }
+
+        if let Some(comment) = &self.trailing_comment {
+            write!(f, " // {comment}")?;
         }
+
+        Ok(())
+    }
+
+    /// Create a copy of this `GOTO` [Command] that targets the marker `to` with an optional
+    /// `fallback`, keeping any condition and trailing comment.
+    pub(crate) fn with_goto_targets(&self, to: &str, fallback: Option<&str>) -> Self {
+        let condition = self
+            .suffix
+            .as_deref()
+            .and_then(|suffix| suffix.split_once(CONDITION_KEYWORD))
+            .map(|(_, condition)| format!("{CONDITION_KEYWORD}{condition}"))
+            .unwrap_or_default();
+
+        self.with_suffix(match fallback {
+            Some(fallback) => format!("%{to}% %{fallback}%{condition}"),
+            None => format!("%{to}%{condition}"),
+        })
+    }
+
+    /// Get the exact text this [Command] was parsed from. Returns `None` for commands that weren't
+    /// parsed from a script.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Get the 1-based line this [Command] was parsed from. Returns `None` for commands that
+    /// weren't parsed from a script.
+    pub fn line(&self) -> Option<usize> {
+        self.line
     }
 
     /// Create a new [Command] from a string.
-    pub fn parse(command_str: &str) -> Result<Self, anyhow::Error> {
-        let mut pairs = Parser::parse(Rule::Command, command_str)?;
+    pub fn parse(command_str: &str) -> Result<Self, ScriptError> {
+        if command_str.trim().is_empty() {
+            return Err(ScriptError::EmptyInput("Command"));
+        }
+
+        let mut pairs =
+            Parser::parse(Rule::Command, command_str).map_err(ScriptError::from_pest)?;
         let pair = pairs.next().expect("a pair exists");
         assert_eq!(pairs.next(), None);
 
+        // Text stops at the end of the line, so anything on the following lines would otherwise be
+        // silently dropped.
+        let rest = &command_str[pair.as_span().end()..];
+        if let Some(offset) = rest.find(|c: char| !c.is_whitespace()) {
+            if rest[..offset].contains('\n') {
+                let end = pair.as_span().end() + offset;
+                return Err(ScriptError::NewlineInCommand {
+                    line: command_str[..end].matches('\n').count() + 1,
+                });
+            }
+        }
+
         pair.try_into()
     }
 }
 
+impl FromStr for Command {
+    type Err = ScriptError;
+
+    fn from_str(command_str: &str) -> Result<Self, Self::Err> {
+        Self::parse(command_str)
+    }
+}
+
 impl TryFrom<Pair<'_, Rule>> for Command {
-    type Error = anyhow::Error;
+    type Error = ScriptError;
 
     fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
         match pair.as_rule() {
             Rule::Command => {
+                let source = pair.as_str().to_owned();
+                let (line, _) = pair.line_col();
                 let inner_pairs = pair.into_inner();
                 let mut prefix = None;
                 let mut command_name = None;
                 let mut suffix = None;
+                let mut trailing_comment = None;
 
                 for pair in inner_pairs {
                     match pair.as_rule() {
@@ -84,24 +544,187 @@ impl TryFrom<Pair<'_, Rule>> for Command {
                             prefix = Some(pair.as_str().trim().to_owned());
                         }
                         Rule::Text => {
-                            suffix = Some(pair.as_str().trim().to_owned());
+                            suffix = Some(unescape(pair.as_str().trim()));
+                        }
+                        Rule::TripleQuotedText => {
+                            // Line endings are normalized so that CRLF scripts format with LF,
+                            // like every other line.
+                            suffix = Some(pair.as_str().replace("\r\n", "\n"));
+                        }
+                        Rule::TrailingComment => {
+                            let text = pair.into_inner().next().expect("comments have text");
+                            trailing_comment = Some(text.as_str().trim_end().to_owned());
                         }
                         _ => unreachable!("hit unexpected pair: {pair}"),
                     }
                 }
 
                 let command_name = command_name.expect("all commands have a name");
+                if prefix.is_some() && command_name.eq_ignore_ascii_case(ELSE_COMMAND) {
+                    return Err(ScriptError::InvalidCommand {
+                        name: ELSE_COMMAND.to_owned(),
+                        reason: "a prefix isn't allowed",
+                    });
+                }
+                let condition = parse_condition(&command_name, suffix.as_deref())?;
 
-                Ok(Self::new(command_name, prefix, suffix))
+                Ok(Self {
+                    source: Some(source),
+                    line: Some(line),
+                    condition,
+                    trailing_comment,
+                    ..Self::new(command_name, prefix, suffix)
+                })
             }
-            _ => bail!("Pair is not a command: {:#?}", pair),
+            rule => Err(ScriptError::unexpected_rule(Rule::Command, rule)),
         }
     }
 }
 
+/// Parse the suffix of a command on its own, without the rest of the command. The suffix is
+/// returned with surrounding whitespace trimmed, like [Command::suffix].
+pub fn parse_command_suffix(suffix_str: &str) -> Result<String, ScriptError> {
+    if suffix_str.trim().is_empty() {
+        return Err(ScriptError::EmptyInput("suffix"));
+    }
+
+    let mut pairs = Parser::parse(Rule::Text, suffix_str).map_err(ScriptError::from_pest)?;
+    let pair = pairs.next().expect("a pair exists");
+    assert_eq!(pairs.next(), None);
+
+    let end = pair.as_span().end();
+    if end < suffix_str.len() {
+        return Err(ScriptError::trailing_input(suffix_str, end));
+    }
+
+    Ok(unescape(pair.as_str().trim()))
+}
+
+/// Get the names of the markers in the suffix of a `GOTO`, with the condition left out.
+fn goto_targets(targets: &str) -> impl Iterator<Item = &str> {
+    targets
+        .split_whitespace()
+        .map(|target| target.trim_start_matches('%').trim_end_matches('%'))
+}
+
+/// Check that the suffix of a `GOTO` is a marker, optionally followed by a fallback marker.
+fn has_valid_goto_targets(suffix: Option<&str>) -> bool {
+    let Some(suffix) = suffix else {
+        return false;
+    };
+    let targets = match suffix.split_once(CONDITION_KEYWORD) {
+        Some((targets, _)) => targets,
+        None => suffix,
+    };
+    let count = targets.split_whitespace().count();
+
+    (1..=2).contains(&count)
+        && targets.split_whitespace().all(|target| {
+            target
+                .strip_prefix('%')
+                .and_then(|target| target.strip_suffix('%'))
+                .is_some_and(is_valid_marker_name)
+        })
+}
+
+/// The delimiter of suffixes that span several lines.
+pub(crate) const TRIPLE_QUOTE: &str = "\"\"\"";
+
+/// Separates the speakers of a `SAY` that several characters say in unison.
+const SPEAKER_SEPARATOR: &str = " & ";
+
+fn is_triple_quoted(text: &str) -> bool {
+    text.len() >= 2 * TRIPLE_QUOTE.len()
+        && text.starts_with(TRIPLE_QUOTE)
+        && text.ends_with(TRIPLE_QUOTE)
+}
+
+/// Resolve the backslash escapes in `text`. Backslashes that don't start an escape are kept.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let escaped = match (c, chars.peek()) {
+            ('\\', Some('|')) => '|',
+            ('\\', Some('n')) => '\n',
+            ('\\', Some('\\')) => '\\',
+            ('\\', Some('/')) => '/',
+            _ => {
+                unescaped.push(c);
+                continue;
+            }
+        };
+        chars.next();
+        unescaped.push(escaped);
+    }
+
+    unescaped
+}
+
+/// Escape the characters in `text` that can't be written directly, the reverse of [unescape].
+/// Backslashes are only escaped when they'd otherwise be read as the start of an escape, and
+/// slashes when they'd otherwise start a trailing comment.
+fn escape(text: &str) -> Cow<'_, str> {
+    if !text.contains(['|', '\n', '\\']) && !text.contains("//") {
+        return Cow::Borrowed(text);
+    }
+
+    let mut escaped = String::with_capacity(text.len());
+    let mut previous = None;
+
+    for (i, c) in text.char_indices() {
+        let rest = &text[i + c.len_utf8()..];
+        match c {
+            '|' => escaped.push_str("\\|"),
+            '\n' => escaped.push_str("\\n"),
+            '\\' if rest.is_empty() || rest.starts_with(['|', '\n', '\\', 'n', '/']) => {
+                escaped.push_str("\\\\")
+            }
+            // A bare `//` at the end of the text doesn't start a trailing comment.
+            '/' if rest.starts_with('/')
+                && previous.is_none_or(char::is_whitespace)
+                && !rest[1..].chars().all(|c| c.is_whitespace() && c != '\n') =>
+            {
+                escaped.push_str("\\/")
+            }
+            c => escaped.push(c),
+        }
+        previous = Some(c);
+    }
+
+    Cow::Owned(escaped)
+}
+
+/// Parse the condition out of a command's suffix, for commands that support one.
+fn parse_condition(name: &str, suffix: Option<&str>) -> Result<Option<Condition>, ScriptError> {
+    if name.eq_ignore_ascii_case(ASSERT_COMMAND) {
+        return suffix.map(Condition::parse).transpose();
+    }
+    if name.eq_ignore_ascii_case(CHOICE_COMMAND) {
+        // The text of a choice can contain " IF " too, so it's only a condition when it parses as
+        // one.
+        let condition = suffix
+            .and_then(|suffix| suffix.rsplit_once(CONDITION_KEYWORD))
+            .and_then(|(_, condition)| Condition::parse(condition).ok());
+        return Ok(condition);
+    }
+    if !name.eq_ignore_ascii_case(GOTO_COMMAND) {
+        return Ok(None);
+    }
+
+    match suffix.and_then(|suffix| suffix.split_once(CONDITION_KEYWORD)) {
+        Some((_, condition)) => Condition::parse(condition).map(Some),
+        None => Ok(None),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Command;
+    use super::{parse_command_suffix, Command, Say, TRIPLE_QUOTE};
+    use crate::script::condition::{Condition, Operator};
+    use crate::script::error::ScriptError;
+    use crate::script::text_table::TextTable;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -120,6 +743,551 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn test_mixed_case_name_is_canonicalized() {
+        let command = Command::parse("Zelda |Say| Hello").expect("command is valid");
+        assert_eq!("Say", command.name());
+        assert_eq!("SAY", command.canonical_name());
+        assert_eq!("Zelda |Say| Hello", command.to_string());
+    }
+
+    #[test]
+    fn test_multi_word_prefix_parse() {
+        let actual = Command::parse("DR. SMITH |SAY| hi").expect("command is valid");
+        let expected = Command::new("SAY", Some("DR. SMITH"), Some("hi"));
+        assert_eq!(expected, actual);
+
+        let actual = Command::parse("STANDARDIZED TEST |SAY| What is the capital of Spain?")
+            .expect("command is valid");
+        assert_eq!(Some("STANDARDIZED TEST"), actual.prefix());
+    }
+
+    #[test]
+    fn test_punctuated_prefix_parse() {
+        let input = "O'BRIEN, JR. (DISGUISED) |SAY| \"Who, me?\"";
+        let command = Command::parse(input).expect("command is valid");
+
+        assert_eq!(Some("O'BRIEN, JR. (DISGUISED)"), command.prefix());
+        assert_eq!(Some("\"Who, me?\""), command.suffix());
+        assert_eq!(input, command.to_string());
+    }
+
+    #[test]
+    fn test_multi_word_prefix_round_trip() {
+        let input = "DR. SMITH |SAY| hi";
+        let output = Command::parse(input).unwrap().to_string();
+        assert_eq!(input, output);
+    }
+
+    #[test]
+    fn test_source_is_preserved() {
+        let input = "ZELDA   |SAY|    \"Hello, world!\"";
+        let command = Command::parse(input).expect("command is valid");
+
+        assert_eq!(Some(input), command.source());
+        assert_eq!("ZELDA |SAY| \"Hello, world!\"", command.to_string());
+        assert_eq!(
+            Command::new("SAY", Some("ZELDA"), Some("\"Hello, world!\"")),
+            command
+        );
+    }
+
+    #[test]
+    fn test_goto_with_condition_parse() {
+        let command = Command::parse("|GOTO| %SHORTCUT% IF flag == 1").expect("command is valid");
+
+        assert_eq!(Some("SHORTCUT"), command.goto_target());
+        assert_eq!(
+            Some(&Condition::new("flag", Operator::Equal, "1")),
+            command.condition()
+        );
+        assert_eq!("|GOTO| %SHORTCUT% IF flag == 1", command.to_string());
+    }
+
+    #[test]
+    fn test_trailing_comment() {
+        let command =
+            Command::parse("ZELDA |SAY| \"Hi\"   // first greeting").expect("command is valid");
+
+        assert_eq!(Some("\"Hi\""), command.suffix());
+        assert_eq!(Some("first greeting"), command.trailing_comment());
+        assert_eq!("ZELDA |SAY| \"Hi\" // first greeting", command.to_string());
+    }
+
+    #[test]
+    fn test_trailing_comment_after_condition() {
+        let command = Command::parse("|GOTO| %SHORTCUT% IF flag == 1 // skip the tutorial")
+            .expect("command is valid");
+
+        assert_eq!(Some("SHORTCUT"), command.goto_target());
+        assert_eq!(
+            Some(&Condition::new("flag", Operator::Equal, "1")),
+            command.condition()
+        );
+        assert_eq!(Some("skip the tutorial"), command.trailing_comment());
+    }
+
+    #[test]
+    fn test_slashes_without_space_are_text() {
+        let command = Command::parse("|SAY| See https://example.com").expect("command is valid");
+
+        assert_eq!(Some("See https://example.com"), command.suffix());
+        assert_eq!(None, command.trailing_comment());
+    }
+
+    #[test]
+    fn test_choice_with_condition() {
+        let command = Command::parse("|CHOICE| [unlock] \"Unlock the door\" IF has_key == true")
+            .expect("command is valid");
+
+        assert_eq!(
+            Some(&Condition::new("has_key", Operator::Equal, "true")),
+            command.condition()
+        );
+        assert_eq!(Some("unlock"), command.choice_key());
+        assert_eq!(Some("\"Unlock the door\""), command.choice_text());
+        assert_eq!(
+            "|CHOICE| [unlock] \"Unlock the door\" IF has_key == true",
+            command.to_string()
+        );
+    }
+
+    #[test]
+    fn test_choice_text_containing_if() {
+        let command = Command::parse("|CHOICE| \"What IF I don't?\"").expect("command is valid");
+
+        assert_eq!(None, command.condition());
+        assert_eq!(Some("\"What IF I don't?\""), command.choice_text());
+    }
+
+    #[test]
+    fn test_predicates() {
+        let say = Command::parse("ZELDA |Say| Hi").expect("command is valid");
+        assert!(say.is_say());
+        assert!(!say.is_choice());
+
+        assert!(Command::new("CHOICE", None, Some("A")).is_choice());
+        assert!(Command::new("GOTO", None, Some("%START%")).is_goto());
+        assert!(Command::new("SET", None, Some("flag = 1")).is_set());
+        assert!(Command::new("TRIGGER", None, Some("door-opens")).is_trigger());
+        assert!(Command::new("IF", None, Some("flag == 1")).is_if());
+        assert!(!Command::new("IF", None, Some("flag == 1")).is_say());
+    }
+
+    #[test]
+    fn test_typed_accessors() {
+        let say = Command::parse("ZELDA |SAY| \"Hey there!\"").expect("command is valid");
+        assert_eq!(
+            Some(Say {
+                speaker: Some("ZELDA"),
+                text: "\"Hey there!\""
+            }),
+            say.as_say()
+        );
+        assert_eq!(None, say.as_goto());
+        assert_eq!(None, say.as_choice());
+
+        let narration = Command::parse("|SAY| It was a dark night.").expect("command is valid");
+        assert_eq!(None, narration.as_say().unwrap().speaker);
+
+        let goto = Command::parse("|GOTO| %START% IF flag == 1").expect("command is valid");
+        assert_eq!(Some("START"), goto.as_goto());
+        assert_eq!(None, goto.as_say());
+
+        let choice = Command::parse("|CHOICE| [yes] \"Yes\"").expect("command is valid");
+        assert_eq!(Some("\"Yes\""), choice.as_choice());
+    }
+
+    #[test]
+    fn test_goto_fallback() {
+        let command =
+            Command::parse("|GOTO| %PREFERRED% %FALLBACK% IF flag == 1").expect("command is valid");
+
+        assert_eq!(Some("PREFERRED"), command.goto_target());
+        assert_eq!(Some("FALLBACK"), command.goto_fallback());
+        assert!(command.condition().is_some());
+
+        let command = Command::parse("|GOTO| %START%").expect("command is valid");
+        assert_eq!(None, command.goto_fallback());
+    }
+
+    #[test]
+    fn test_speakers() {
+        let input = "ZELDA & LINK |SAY| \"Surprise!\"";
+        let command = Command::parse(input).expect("command is valid");
+
+        assert_eq!(vec!["ZELDA", "LINK"], command.speakers());
+        assert_eq!(Some("ZELDA & LINK"), command.prefix());
+        assert_eq!(input, command.to_string());
+
+        let command = Command::parse("ZELDA |SAY| Hi").expect("command is valid");
+        assert_eq!(vec!["ZELDA"], command.speakers());
+
+        let command = Command::parse("ZELDA  &  LINK |SAY| Hi").expect("command is valid");
+        assert_eq!(vec!["ZELDA", "LINK"], command.speakers());
+
+        let command = Command::parse("|SAY| Hi").expect("command is valid");
+        assert!(command.speakers().is_empty());
+
+        let command = Command::parse("A & B |SET| x = 1").expect("command is valid");
+        assert!(command.speakers().is_empty());
+    }
+
+    #[test]
+    fn test_unquoted_suffix() {
+        let unquoted = |input: &str| {
+            Command::parse(input)
+                .expect("command is valid")
+                .unquoted_suffix()
+                .map(|suffix| suffix.into_owned())
+        };
+
+        assert_eq!(
+            Some("Hey there!".to_owned()),
+            unquoted("ZELDA |SAY| \"Hey there!\"")
+        );
+        assert_eq!(
+            Some("It was a dark night.".to_owned()),
+            unquoted("|SAY| It was a dark night.")
+        );
+        assert_eq!(
+            Some("\"Nested\"".to_owned()),
+            unquoted("|SAY| \"\"Nested\"\"")
+        );
+        assert_eq!(Some("\"".to_owned()), unquoted("|SAY| \""));
+        assert_eq!(
+            Some("Line one\nLine two".to_owned()),
+            unquoted("|SAY| \"\"\"\nLine one\nLine two\n\"\"\"")
+        );
+    }
+
+    #[test]
+    fn test_with_suffix() {
+        let command = Command::parse("ZELDA |SAY| \"Hey there!\"").expect("command is valid");
+        let modified = command.with_suffix("\"Hello there!\"");
+
+        assert_eq!("ZELDA |SAY| \"Hello there!\"", modified.to_string());
+        assert_eq!(None, modified.source());
+        assert_eq!(
+            modified,
+            Command::parse(&modified.to_string()).expect("command is valid")
+        );
+        assert_eq!("ZELDA |SAY| \"Hey there!\"", command.to_string());
+    }
+
+    #[test]
+    fn test_set_prefix_and_suffix() {
+        let mut command = Command::parse("|GOTO| %SHORTCUT%").expect("command is valid");
+        command.set_suffix("%SHORTCUT% IF flag == 1");
+        assert_eq!(
+            Some(&Condition::new("flag", Operator::Equal, "1")),
+            command.condition()
+        );
+
+        let mut command = Command::parse("ZELDA |SAY| Hi").expect("command is valid");
+        command.set_prefix("LINK");
+        assert_eq!("LINK |SAY| Hi", command.to_string());
+    }
+
+    #[test]
+    fn test_escaped_pipe() {
+        let input = "ZELDA |SAY| \"Either a \\| b.\"";
+        let command = Command::parse(input).expect("command is valid");
+
+        assert_eq!(Some("\"Either a | b.\""), command.suffix());
+        assert_eq!(input, command.to_string());
+    }
+
+    #[test]
+    fn test_escaped_newline() {
+        let input = "|SAY| First line.\\nSecond line.";
+        let command = Command::parse(input).expect("command is valid");
+
+        assert_eq!(Some("First line.\nSecond line."), command.suffix());
+        assert_eq!(input, command.to_string());
+    }
+
+    #[test]
+    fn test_escaped_backslash_and_quote() {
+        let command = Command::parse(r#"|SAY| "C:\\new" or C:\old"#).expect("command is valid");
+        assert_eq!(Some(r#""C:\new" or C:\old"#), command.suffix());
+        assert_eq!(r#"|SAY| "C:\\new" or C:\old"#, command.to_string());
+
+        // Quotes don't need escaping, so a backslash before one is kept as written.
+        let input = r#"|SAY| \"hi\""#;
+        let command = Command::parse(input).expect("command is valid");
+        assert_eq!(Some(r#"\"hi\""#), command.suffix());
+        assert_eq!(input, command.to_string());
+
+        let command = Command::new("SAY", None, Some("ends with \\"));
+        let round_tripped = Command::parse(&command.to_string()).expect("command is valid");
+        assert_eq!(command, round_tripped);
+    }
+
+    #[test]
+    fn test_escaped_slash() {
+        let command = Command::parse(r"|SAY| see \//docs // a comment").expect("command is valid");
+        assert_eq!(Some("see //docs"), command.suffix());
+        assert_eq!(Some("a comment"), command.trailing_comment());
+
+        let command = Command::new("SAY", None, Some("a // b"));
+        assert_eq!(r"|SAY| a \// b", command.to_string());
+    }
+
+    #[test]
+    fn test_bare_trailing_slashes_are_text() {
+        let command = Command::parse("|SAY| a //").expect("command is valid");
+        assert_eq!(Some("a //"), command.suffix());
+        assert_eq!(None, command.trailing_comment());
+        assert_eq!("|SAY| a //", command.to_string());
+    }
+
+    #[test]
+    fn test_escaped_suffixes_round_trip() {
+        let pieces = ["a", " ", "/", "//", "|", "\\", "\n", "n", "\""];
+        let mut suffixes = vec![String::new()];
+        for _ in 0..4 {
+            suffixes = suffixes
+                .iter()
+                .flat_map(|suffix| pieces.iter().map(move |piece| format!("{suffix}{piece}")))
+                .collect();
+
+            for suffix in &suffixes {
+                // Surrounding whitespace is trimmed when parsing, and triple quotes are verbatim.
+                if suffix.trim() != suffix || suffix.starts_with(TRIPLE_QUOTE) {
+                    continue;
+                }
+
+                let command = Command::new("SAY".to_owned(), None, Some(suffix.clone()));
+                let round_tripped = Command::parse(&command.to_string())
+                    .unwrap_or_else(|err| panic!("{command} doesn't parse: {err}"));
+                assert_eq!(command, round_tripped, "{command}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_triple_quoted_suffix() {
+        let input = "NARRATOR |SAY| \"\"\"
+It was a dark and stormy night.
+The rain fell in torrents | except at occasional intervals.
+Nobody was out.
+\"\"\"";
+        let command = Command::parse(input).expect("command is valid");
+
+        assert_eq!(Some("NARRATOR"), command.prefix());
+        assert_eq!(Some(&input["NARRATOR |SAY| ".len()..]), command.suffix());
+        assert_eq!(5, command.suffix().unwrap().lines().count());
+        assert_eq!(input, command.to_string());
+    }
+
+    #[test]
+    fn test_parse_command_suffix() {
+        assert_eq!(
+            "\"Hello, world!\"",
+            parse_command_suffix(" \"Hello, world!\" ").expect("suffix is valid")
+        );
+    }
+
+    #[test]
+    fn test_invalid_command_suffix_is_an_error() {
+        assert!(matches!(
+            parse_command_suffix("Hello |SAY| world").unwrap_err(),
+            ScriptError::Syntax { column: 7, .. }
+        ));
+        assert!(matches!(
+            parse_command_suffix("Hello\nworld").unwrap_err(),
+            ScriptError::Syntax { line: 1, .. }
+        ));
+        assert_eq!(
+            ScriptError::EmptyInput("suffix"),
+            parse_command_suffix("").unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_localized_suffix() {
+        let mut table = TextTable::new();
+        table.insert("fr", "accept", "\"J'accepte\"");
+        let command = Command::parse("|CHOICE| [accept] \"I accept\"").expect("command is valid");
+
+        assert_eq!(
+            Some("\"J'accepte\"".to_owned()),
+            command.localized_suffix(&table, "fr")
+        );
+        assert_eq!(
+            Some("\"I accept\"".to_owned()),
+            command.localized_suffix(&table, "de")
+        );
+    }
+
+    #[test]
+    fn test_localized_suffix_falls_back_without_key() {
+        let table = TextTable::new();
+        let command = Command::parse("ZELDA |SAY| \"Hey there!\"").expect("command is valid");
+
+        assert_eq!(
+            Some("\"Hey there!\"".to_owned()),
+            command.localized_suffix(&table, "fr")
+        );
+    }
+
+    #[test]
+    fn test_goto_without_condition_parse() {
+        let command = Command::parse("|GOTO| %START%").expect("command is valid");
+
+        assert_eq!(Some("START"), command.goto_target());
+        assert_eq!(None, command.condition());
+    }
+
+    #[test]
+    fn test_goto_with_invalid_condition_is_an_error() {
+        let err = Command::parse("|GOTO| %SHORTCUT% IF flag").unwrap_err();
+
+        assert!(matches!(err, ScriptError::Syntax { .. }));
+    }
+
+    #[test]
+    fn test_keyed_choice_parse() {
+        let input = "|CHOICE| [accept] \"I accept\"";
+        let command = Command::parse(input).expect("command is valid");
+
+        assert_eq!(Some("accept"), command.choice_key());
+        assert_eq!(Some("\"I accept\""), command.choice_text());
+        assert_eq!(input, command.to_string());
+    }
+
+    #[test]
+    fn test_choice_key_edge_cases() {
+        let command = Command::parse("|CHOICE| [Shout] at the sky").expect("command is valid");
+        assert_eq!(Some("Shout"), command.choice_key());
+
+        let command = Command::parse("|CHOICE| [shout loudly]").expect("command is valid");
+        assert_eq!(None, command.choice_key());
+        assert_eq!(Some("[shout loudly]"), command.choice_text());
+
+        let command = Command::parse("|SAY| [accept] Hi").expect("command is valid");
+        assert_eq!(None, command.choice_key());
+        assert_eq!(None, command.choice_text());
+    }
+
+    #[test]
+    fn test_newline_in_suffix_is_an_error() {
+        let err = Command::parse("ZELDA |SAY| \"Hello,\n world!\"").unwrap_err();
+
+        assert_eq!(ScriptError::NewlineInCommand { line: 2 }, err);
+    }
+
+    #[test]
+    fn test_trailing_newline_is_allowed() {
+        let command = Command::parse("|SAY| Hello\n\n").expect("command is valid");
+
+        assert_eq!(Some("Hello"), command.suffix());
+    }
+
+    #[test]
+    fn test_assert_parse() {
+        let command = Command::parse("|ASSERT| gold == 100").expect("command is valid");
+
+        assert_eq!(
+            Some(&Condition::new("gold", Operator::Equal, "100")),
+            command.condition()
+        );
+        assert_eq!("|ASSERT| gold == 100", command.to_string());
+    }
+
+    #[test]
+    fn test_assert_with_invalid_condition_is_an_error() {
+        let err = Command::parse("|ASSERT| gold is plenty").unwrap_err();
+
+        assert!(matches!(err, ScriptError::Syntax { .. }));
+    }
+
+    #[test]
+    fn test_try_new_accepts_valid_commands() {
+        let command =
+            Command::try_new("SAY", Some("ZELDA"), Some("Hey there!")).expect("command is valid");
+        assert_eq!(
+            Command::new("SAY", Some("ZELDA"), Some("Hey there!")),
+            command
+        );
+
+        assert!(Command::try_new("TRIGGER", Some("ZELDA"), None).is_ok());
+        assert!(Command::try_new("ELSE", None, None).is_ok());
+    }
+
+    #[test]
+    fn test_bare_else() {
+        let command = Command::parse("|ELSE|").expect("command is valid");
+        assert_eq!(Command::new("ELSE", None, None), command);
+
+        let command = Command::try_new("ELSE", None, None).expect("command is valid");
+        assert_eq!(command, Command::parse(&command.to_string()).unwrap());
+
+        let command = Command::parse("|else| // otherwise").expect("command is valid");
+        assert_eq!(None, command.suffix());
+        assert_eq!(Some("otherwise"), command.trailing_comment());
+
+        assert!(Command::parse("|SAY|").is_err());
+        assert_eq!(
+            Err(ScriptError::InvalidCommand {
+                name: "ELSE".to_owned(),
+                reason: "a prefix isn't allowed"
+            }),
+            Command::parse("ZELDA |else|")
+        );
+    }
+
+    #[test]
+    fn test_try_new_rejects_prefixes() {
+        for name in ["ASSERT", "CHOICE", "ELSE", "GOTO", "INCLUDE"] {
+            let err = Command::try_new(name, Some("ZELDA"), Some("%START%")).unwrap_err();
+            assert_eq!(
+                ScriptError::InvalidCommand {
+                    name: name.to_owned(),
+                    reason: "a prefix isn't allowed"
+                },
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_new_rejects_missing_suffixes() {
+        for name in ["ASSERT", "CHOICE", "GOTO", "INCLUDE", "SAY"] {
+            let err = Command::try_new(name, None, None).unwrap_err();
+            assert_eq!(
+                ScriptError::InvalidCommand {
+                    name: name.to_owned(),
+                    reason: "a suffix is required"
+                },
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_new_checks_goto_targets() {
+        assert!(Command::try_new("GOTO", None, Some("%PREFERRED% %FALLBACK%")).is_ok());
+
+        for suffix in ["START", "%start%", "%A% fallback", "%A% %B% %C%"] {
+            let err = Command::try_new("GOTO", None, Some(suffix)).unwrap_err();
+            assert_eq!(
+                ScriptError::InvalidCommand {
+                    name: "GOTO".to_owned(),
+                    reason: "the suffix must be a marker, optionally followed by a fallback marker"
+                },
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_new_rejects_invalid_conditions() {
+        let err = Command::try_new("GOTO", None, Some("%START% IF flag")).unwrap_err();
+        assert!(matches!(err, ScriptError::Syntax { .. }));
+    }
+
     #[test]
     fn test_round_trip() {
         let input = "ZELDA |SAY| \"Hello, world!\"";
@@ -127,4 +1295,24 @@ mod tests {
         let output = command.to_string();
         assert_eq!(input, output);
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_recomputes_condition() {
+        let command = Command::parse("|GOTO| %END% IF gold >= 10").expect("command is valid");
+        let json = serde_json::to_string(&command).expect("a command can be serialized");
+        assert!(!json.contains("condition"));
+        assert!(!json.contains("line"));
+
+        let edited = json.replace("gold >= 10", "gems >= 3");
+        let deserialized: Command =
+            serde_json::from_str(&edited).expect("a command can be deserialized");
+        assert_eq!(
+            Some(&Condition::parse("gems >= 3").unwrap()),
+            deserialized.condition()
+        );
+
+        let invalid = json.replace("gold >= 10", "gold");
+        assert!(serde_json::from_str::<Command>(&invalid).is_err());
+    }
 }
@@ -0,0 +1,153 @@
+//! # Command specs
+//!
+//! A [CommandSpec] describes the shape of a command: whether it takes a prefix, a suffix, and a
+//! block after it. Built-in commands are checked against their specs when created with
+//! [Command::try_new], and custom commands can declare a spec of their own to get the same checks
+//! and error messages.
+
+use crate::script::{
+    block::Block,
+    command::{
+        Command, ASSERT_COMMAND, CHOICE_COMMAND, ELSE_COMMAND, GOTO_COMMAND, IF_COMMAND,
+        INCLUDE_COMMAND, SAY_COMMAND, SET_COMMAND, TRIGGER_COMMAND,
+    },
+    error::ScriptError,
+};
+
+/// Whether a part of a command must, may, or must not be present.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Arity {
+    /// The part must be present.
+    Required,
+    /// The part may be present.
+    Optional,
+    /// The part must not be present.
+    Forbidden,
+}
+
+impl Arity {
+    /// Check whether a part with this [Arity] may be present or absent, as given by `present`.
+    fn allows(self, present: bool) -> bool {
+        match self {
+            Arity::Required => present,
+            Arity::Optional => true,
+            Arity::Forbidden => !present,
+        }
+    }
+}
+
+/// The shape of a command: which of its prefix, suffix, and following block it takes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CommandSpec {
+    /// Whether the command takes a prefix.
+    pub prefix: Arity,
+    /// Whether the command takes a suffix.
+    pub suffix: Arity,
+    /// Whether the command takes a block after it.
+    pub block: Arity,
+}
+
+impl CommandSpec {
+    /// Get the [CommandSpec] of the built-in command with the canonical name `name`, if there is
+    /// one.
+    pub fn builtin(name: &str) -> Option<Self> {
+        let (prefix, suffix) = match name {
+            ASSERT_COMMAND | CHOICE_COMMAND | GOTO_COMMAND | INCLUDE_COMMAND => {
+                (Arity::Forbidden, Arity::Required)
+            }
+            ELSE_COMMAND => (Arity::Forbidden, Arity::Optional),
+            SAY_COMMAND => (Arity::Optional, Arity::Required),
+            IF_COMMAND | SET_COMMAND | TRIGGER_COMMAND => (Arity::Optional, Arity::Optional),
+            _ => return None,
+        };
+
+        Some(Self {
+            prefix,
+            suffix,
+            block: Arity::Optional,
+        })
+    }
+
+    /// Check that `command`, followed by `block` if it has one, has the shape described by this
+    /// [CommandSpec].
+    pub fn check(&self, command: &Command, block: Option<&Block>) -> Result<(), ScriptError> {
+        // Each part, with the reasons it's invalid when it's missing and when it's present.
+        let parts = [
+            (
+                self.prefix,
+                command.prefix().is_some(),
+                ("a prefix is required", "a prefix isn't allowed"),
+            ),
+            (
+                self.suffix,
+                command.suffix().is_some(),
+                ("a suffix is required", "a suffix isn't allowed"),
+            ),
+            (
+                self.block,
+                block.is_some(),
+                ("a block is required", "a block isn't allowed"),
+            ),
+        ];
+
+        for (arity, present, (missing, unexpected)) in parts {
+            if !arity.allows(present) {
+                return Err(ScriptError::InvalidCommand {
+                    name: command.canonical_name().into_owned(),
+                    reason: if present { unexpected } else { missing },
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arity, CommandSpec};
+    use crate::script::{block::Block, command::Command, error::ScriptError};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_custom_spec() {
+        let spec = CommandSpec {
+            prefix: Arity::Required,
+            suffix: Arity::Forbidden,
+            block: Arity::Required,
+        };
+        let block = Block::parse("    |SAY| Hi\n").expect("block is valid");
+
+        let command = Command::new("WAIT-FOR", Some("ZELDA"), None);
+        assert!(spec.check(&command, Some(&block)).is_ok());
+
+        let invalid_command = |reason| ScriptError::InvalidCommand {
+            name: "WAIT-FOR".to_owned(),
+            reason,
+        };
+        assert_eq!(
+            Err(invalid_command("a block is required")),
+            spec.check(&command, None)
+        );
+        assert_eq!(
+            Err(invalid_command("a prefix is required")),
+            spec.check(&Command::new("WAIT-FOR", None, None), Some(&block))
+        );
+        assert_eq!(
+            Err(invalid_command("a suffix isn't allowed")),
+            spec.check(
+                &Command::new("WAIT-FOR", Some("ZELDA"), Some("now")),
+                Some(&block)
+            )
+        );
+    }
+
+    #[test]
+    fn test_builtin_specs() {
+        assert_eq!(None, CommandSpec::builtin("PLAY-SOUND"));
+
+        let say = CommandSpec::builtin("SAY").expect("SAY is built in");
+        assert_eq!(Arity::Optional, say.prefix);
+        assert_eq!(Arity::Required, say.suffix);
+    }
+}
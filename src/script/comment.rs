@@ -5,15 +5,29 @@
 //! ```text
 //! // This is a comment
 //! ```
+//!
+//! A command can be disabled by commenting it out. It's skipped, but it's kept in the script so it
+//! can be enabled again later.
+//!
+//! ```text
+//! // ZELDA |SAY| "This line is disabled for now."
+//! ```
+//!
+//! Comments that follow a command on the same line belong to the command, see
+//! [Command::trailing_comment].
 
-use crate::script::parser::{Parser, Rule};
-use anyhow::bail;
+use crate::script::{
+    command::Command,
+    error::ScriptError,
+    parser::{Parser, Rule},
+};
 use pest::iterators::Pair;
 use pest::Parser as PestParser;
-use std::fmt;
+use std::{fmt, str::FromStr};
 
 /// A comment in a script.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Comment {
     text: String,
 }
@@ -30,18 +44,90 @@ impl Comment {
         Self { text }
     }
 
+    /// Create a new [Comment] that disables `command`. Returns `None` if the command spans several
+    /// lines, like one with triple-quoted text, since comments can't.
+    pub fn disabled(command: &Command) -> Option<Self> {
+        let text = command.to_string();
+        if text.contains('\n') {
+            return None;
+        }
+
+        Some(Self::new(text))
+    }
+
+    /// Get the [Command] disabled by this [Comment], if its text is a valid command.
+    pub fn as_disabled_command(&self) -> Option<Command> {
+        Command::parse(&self.text).ok()
+    }
+
     /// Parse a [Comment] from a string.
-    pub fn parse(comment_str: &str) -> Result<Self, anyhow::Error> {
-        let mut pairs = Parser::parse(Rule::Comment, comment_str)?;
+    pub fn parse(comment_str: &str) -> Result<Self, ScriptError> {
+        if comment_str.trim().is_empty() {
+            return Err(ScriptError::EmptyInput("Comment"));
+        }
+
+        let mut pairs =
+            Parser::parse(Rule::Comment, comment_str).map_err(ScriptError::from_pest)?;
         let pair = pairs.next().expect("a pair exists");
         assert_eq!(pairs.next(), None);
 
         pair.try_into()
     }
+
+    /// Get the text of this [Comment], excluding the leading `//`.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the `@key: value` directives embedded in this [Comment], in the order they appear.
+    ///
+    /// A directive is an `@` at the beginning of the comment or after whitespace, followed by a key
+    /// made of letters, digits, `-`, and `_`, and a `:`. Its value runs until the next directive or
+    /// the end of the comment, so it can contain other `@`s. Text that isn't part of a directive is
+    /// ignored. The raw text is left untouched, so comments still round-trip exactly.
+    pub fn directives(&self) -> Vec<(String, String)> {
+        // The start of each directive, and where its value starts.
+        let mut directives = Vec::new();
+        let mut previous = None;
+        for (i, c) in self.text.char_indices() {
+            if c == '@' && previous.is_none_or(char::is_whitespace) {
+                let rest = &self.text[i + 1..];
+                let key_len = rest
+                    .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+                    .unwrap_or(rest.len());
+                if key_len > 0 && rest[key_len..].starts_with(':') {
+                    directives.push((i, i + 1 + key_len));
+                }
+            }
+            previous = Some(c);
+        }
+
+        directives
+            .iter()
+            .enumerate()
+            .map(|(n, &(start, key_end))| {
+                let end = directives
+                    .get(n + 1)
+                    .map_or(self.text.len(), |&(next_start, _)| next_start);
+                let key = &self.text[start + 1..key_end];
+                let value = &self.text[key_end + 1..end];
+
+                (key.to_owned(), value.trim().to_owned())
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Comment {
+    type Err = ScriptError;
+
+    fn from_str(comment_str: &str) -> Result<Self, Self::Err> {
+        Self::parse(comment_str)
+    }
 }
 
 impl TryFrom<Pair<'_, Rule>> for Comment {
-    type Error = anyhow::Error;
+    type Error = ScriptError;
 
     fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
         match pair.as_rule() {
@@ -53,7 +139,7 @@ impl TryFrom<Pair<'_, Rule>> for Comment {
 
                 Ok(Self::new(text))
             }
-            _ => bail!("Pair is not a comment: {:#?}", pair),
+            rule => Err(ScriptError::unexpected_rule(Rule::Comment, rule)),
         }
     }
 }
@@ -61,6 +147,7 @@ impl TryFrom<Pair<'_, Rule>> for Comment {
 #[cfg(test)]
 mod tests {
     use super::Comment;
+    use crate::script::command::Command;
 
     #[test]
     fn test_comment_parse() {
@@ -68,6 +155,67 @@ mod tests {
         assert_eq!(comment.text, "This is a comment");
     }
 
+    #[test]
+    fn test_directives_parse() {
+        let comment = Comment::parse("// @voice: calm @portrait: angry\n").unwrap();
+        assert_eq!(
+            comment.directives(),
+            vec![
+                ("voice".to_owned(), "calm".to_owned()),
+                ("portrait".to_owned(), "angry".to_owned()),
+            ]
+        );
+        assert_eq!(comment.to_string(), "// @voice: calm @portrait: angry\n");
+    }
+
+    #[test]
+    fn test_directive_value_with_at() {
+        let comment = Comment::parse("// @note: email me @ home @author: zelda\n").unwrap();
+        assert_eq!(
+            comment.directives(),
+            vec![
+                ("note".to_owned(), "email me @ home".to_owned()),
+                ("author".to_owned(), "zelda".to_owned()),
+            ]
+        );
+
+        let comment = Comment::parse("// @note: ping @zelda about it\n").unwrap();
+        assert_eq!(
+            comment.directives(),
+            vec![("note".to_owned(), "ping @zelda about it".to_owned())]
+        );
+    }
+
+    #[test]
+    fn test_comment_without_directives() {
+        let comment = Comment::parse("// email me at zelda@hyrule.com: thanks\n").unwrap();
+        assert!(comment.directives().is_empty());
+    }
+
+    #[test]
+    fn test_toggle_disabled_command() {
+        let command = Command::parse("ZELDA |SAY| \"Hey there!\"").unwrap();
+
+        let comment = Comment::disabled(&command).expect("command fits on one line");
+        assert_eq!("// ZELDA |SAY| \"Hey there!\"\n", comment.to_string());
+
+        let comment = Comment::parse(&comment.to_string()).unwrap();
+        assert_eq!(Some(command), comment.as_disabled_command());
+    }
+
+    #[test]
+    fn test_multi_line_command_cant_be_disabled() {
+        let command = Command::parse("|SAY| \"\"\"\nFirst\nSecond\n\"\"\"").unwrap();
+
+        assert_eq!(None, Comment::disabled(&command));
+    }
+
+    #[test]
+    fn test_regular_comment_is_not_a_disabled_command() {
+        let comment = Comment::parse("// This is a comment\n").unwrap();
+        assert_eq!(None, comment.as_disabled_command());
+    }
+
     #[test]
     fn test_round_trip() {
         let input = "// This is a comment\n";
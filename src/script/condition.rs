@@ -0,0 +1,183 @@
+//! # Conditions
+//!
+//! Conditions compare a variable from the game state against a value. They're written as the name of
+//! the variable, a comparison operator, and the value to compare against.
+//!
+//! ```text
+//! |GOTO| %SHORTCUT% IF flag == 1
+//! ```
+//!
+//! The supported operators are `==`, `!=`, `<`, `<=`, `>`, and `>=`.
+
+use crate::script::{
+    error::ScriptError,
+    parser::{Parser, Rule},
+};
+use pest::{iterators::Pair, Parser as PestParser};
+use std::fmt;
+
+/// A comparison operator used in a [Condition].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Operator {
+    /// `==`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `<`
+    LessThan,
+    /// `<=`
+    LessThanOrEqual,
+    /// `>`
+    GreaterThan,
+    /// `>=`
+    GreaterThanOrEqual,
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operator = match self {
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::LessThan => "<",
+            Self::LessThanOrEqual => "<=",
+            Self::GreaterThan => ">",
+            Self::GreaterThanOrEqual => ">=",
+        };
+
+        write!(f, "{operator}")
+    }
+}
+
+/// A condition comparing a variable against a value.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Condition {
+    variable: String,
+    operator: Operator,
+    value: String,
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.variable, self.operator, self.value)
+    }
+}
+
+impl Condition {
+    /// Create a new [Condition].
+    pub fn new(variable: impl Into<String>, operator: Operator, value: impl Into<String>) -> Self {
+        Self {
+            variable: variable.into(),
+            operator,
+            value: value.into(),
+        }
+    }
+
+    /// Get the name of the variable this [Condition] checks.
+    pub fn variable(&self) -> &str {
+        &self.variable
+    }
+
+    /// Get the [Operator] used to compare the variable and the value.
+    pub fn operator(&self) -> Operator {
+        self.operator
+    }
+
+    /// Get the value the variable is compared against, exactly as it was written.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Parse a [Condition] from a string.
+    pub fn parse(condition_str: &str) -> Result<Self, ScriptError> {
+        if condition_str.trim().is_empty() {
+            return Err(ScriptError::EmptyInput("Condition"));
+        }
+
+        let mut pairs =
+            Parser::parse(Rule::Condition, condition_str).map_err(ScriptError::from_pest)?;
+        let pair = pairs.next().expect("a pair exists");
+        assert_eq!(pairs.next(), None);
+
+        pair.try_into()
+    }
+}
+
+impl TryFrom<Pair<'_, Rule>> for Condition {
+    type Error = ScriptError;
+
+    fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
+        match pair.as_rule() {
+            Rule::Condition => {
+                let mut variable = None;
+                let mut operator = None;
+                let mut value = None;
+
+                for pair in pair.into_inner() {
+                    match pair.as_rule() {
+                        Rule::Variable => variable = Some(pair.as_str().to_owned()),
+                        Rule::Operator => {
+                            operator = Some(match pair.as_str() {
+                                "==" => Operator::Equal,
+                                "!=" => Operator::NotEqual,
+                                "<" => Operator::LessThan,
+                                "<=" => Operator::LessThanOrEqual,
+                                ">" => Operator::GreaterThan,
+                                ">=" => Operator::GreaterThanOrEqual,
+                                op => unreachable!("the grammar doesn't allow operator {op}"),
+                            })
+                        }
+                        Rule::Text => value = Some(pair.as_str().trim().to_owned()),
+                        _ => unreachable!("hit unexpected pair: {pair}"),
+                    }
+                }
+
+                Ok(Self {
+                    variable: variable.expect("all conditions have a variable"),
+                    operator: operator.expect("all conditions have an operator"),
+                    value: value.expect("all conditions have a value"),
+                })
+            }
+            rule => Err(ScriptError::unexpected_rule(Rule::Condition, rule)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Condition, Operator};
+    use crate::script::error::ScriptError;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_condition_parse() {
+        let expected = Condition::new("coins", Operator::GreaterThanOrEqual, "3");
+        let actual = Condition::parse("coins >= 3").expect("condition is valid");
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_condition_without_spaces_parse() {
+        let expected = Condition::new("met_zelda", Operator::NotEqual, "true");
+        let actual = Condition::parse("met_zelda!=true").expect("condition is valid");
+
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_condition_without_operator_is_an_error() {
+        let err = Condition::parse("flag 1").unwrap_err();
+
+        assert!(matches!(err, ScriptError::Syntax { .. }));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let input = "name == \"Link\"";
+        let output = Condition::parse(input).unwrap().to_string();
+
+        assert_eq!(input, output);
+    }
+}
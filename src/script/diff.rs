@@ -0,0 +1,165 @@
+//! # Diffs
+//!
+//! Element-level diffs between two [Scripts](crate::script::Script). Blocks are flattened before
+//! comparing, so changes in indentation alone don't show up as changes to the script's content.
+
+use super::element::TopLevelElement;
+use std::fmt;
+
+/// A single difference between two scripts.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ElementChange {
+    /// An element that only exists in the new script.
+    Added(TopLevelElement),
+    /// An element that only exists in the old script.
+    Removed(TopLevelElement),
+    /// An element in the old script that was replaced by a different element in the new script.
+    Changed {
+        /// The element in the old script.
+        from: TopLevelElement,
+        /// The element in the new script.
+        to: TopLevelElement,
+    },
+}
+
+impl fmt::Display for ElementChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Added(el) => write!(f, "+ {el}"),
+            Self::Removed(el) => write!(f, "- {el}"),
+            Self::Changed { from, to } => write!(f, "- {from}+ {to}"),
+        }
+    }
+}
+
+/// Compute the changes needed to turn `old` into `new`, using the longest common subsequence of
+/// their flattened elements.
+pub(crate) fn diff(old: &[TopLevelElement], new: &[TopLevelElement]) -> Vec<ElementChange> {
+    let old = flatten(old);
+    let new = flatten(new);
+
+    // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            flush(&mut changes, &mut removed, &mut added);
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            added.push(new[j].clone());
+            j += 1;
+        } else {
+            removed.push(old[i].clone());
+            i += 1;
+        }
+    }
+    flush(&mut changes, &mut removed, &mut added);
+
+    changes
+}
+
+/// Pair up a run of removed and added elements as changes, then record whatever is left over.
+fn flush(
+    changes: &mut Vec<ElementChange>,
+    removed: &mut Vec<TopLevelElement>,
+    added: &mut Vec<TopLevelElement>,
+) {
+    let paired = removed.len().min(added.len());
+    let mut removed = removed.drain(..);
+    let mut added = added.drain(..);
+
+    for (from, to) in removed.by_ref().zip(added.by_ref()).take(paired) {
+        changes.push(ElementChange::Changed { from, to });
+    }
+    changes.extend(removed.map(ElementChange::Removed));
+    changes.extend(added.map(ElementChange::Added));
+}
+
+fn flatten(elements: &[TopLevelElement]) -> Vec<&TopLevelElement> {
+    let mut flattened = Vec::new();
+    for el in elements {
+        match el {
+            TopLevelElement::Block(block) => flattened.extend(flatten(block.elements())),
+            el => flattened.push(el),
+        }
+    }
+
+    flattened
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ElementChange;
+    use crate::script::{command::Command, Script};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_diff_with_inserted_line() {
+        let old = Script::parse(
+            "%START%
+ZELDA |SAY| \"Hey there!\"
+    |CHOICE| \"Hello!\"
+        YOU |SAY| \"Hello!\"
+%END%
+",
+        )
+        .unwrap();
+        // Reindented, with one new line.
+        let new = Script::parse(
+            "%START%
+ZELDA |SAY| \"Hey there!\"
+ZELDA |SAY| \"How are you?\"
+|CHOICE| \"Hello!\"
+YOU |SAY| \"Hello!\"
+%END%
+",
+        )
+        .unwrap();
+
+        let expected = vec![ElementChange::Added(
+            Command::new("SAY", Some("ZELDA"), Some("\"How are you?\"")).into(),
+        )];
+
+        assert_eq!(expected, old.diff(&new));
+    }
+
+    #[test]
+    fn test_diff_with_changed_line() {
+        let old = Script::parse("%START%\n|SAY| A\n|SAY| B\n%END%\n").unwrap();
+        let new = Script::parse("%START%\n|SAY| A\n|SAY| C\n%END%\n").unwrap();
+
+        let changes = old.diff(&new);
+
+        assert_eq!(
+            vec![ElementChange::Changed {
+                from: Command::new("SAY", None, Some("B")).into(),
+                to: Command::new("SAY", None, Some("C")).into(),
+            }],
+            changes
+        );
+        assert_eq!("- |SAY| B\n+ |SAY| C\n", changes[0].to_string());
+    }
+
+    #[test]
+    fn test_diff_of_identical_scripts_is_empty() {
+        let input = std::fs::read_to_string("example_scripts/daisy-and-luigi.script")
+            .expect("example script exists");
+        let script = Script::parse(&input).unwrap();
+
+        assert!(script.diff(&script).is_empty());
+    }
+}
@@ -7,6 +7,7 @@ use std::fmt;
 
 /// A top-level element in a script. Either a [Block], [Line], or [Comment].
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TopLevelElement {
     /// A [Block].
     Block(Block),
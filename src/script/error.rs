@@ -0,0 +1,178 @@
+//! # Errors
+//!
+//! The errors that can occur when parsing a script or any of its parts.
+
+use crate::script::parser::Rule;
+use pest::{
+    error::{ErrorVariant, LineColLocation},
+    Position,
+};
+
+/// An error that occurred while parsing a script or any of its parts.
+#[derive(Debug, PartialEq, Eq, Clone, thiserror::Error)]
+pub enum ScriptError {
+    /// The input doesn't follow the script syntax.
+    #[error("Invalid syntax at line {line}, column {column}:\n{message}")]
+    Syntax {
+        /// The 1-based line where the error occurred.
+        line: usize,
+        /// The 1-based column where the error occurred.
+        column: usize,
+        /// A description of the error, including a snippet of the offending input.
+        message: String,
+    },
+    /// A parsed element was converted into the wrong type.
+    #[error("Expected a {expected} but found a {found}")]
+    UnexpectedRule {
+        /// The name of the element that was expected.
+        expected: &'static str,
+        /// The name of the element that was found.
+        found: &'static str,
+    },
+    /// A single-line command continued onto another line.
+    #[error("Commands must fit on a single line, but this one continues on line {line}")]
+    NewlineInCommand {
+        /// The 1-based line the command continued onto.
+        line: usize,
+    },
+    /// A command doesn't follow the rules for its kind.
+    #[error("Invalid {name} command: {reason}")]
+    InvalidCommand {
+        /// The canonical name of the command.
+        name: String,
+        /// The rule the command breaks.
+        reason: &'static str,
+    },
+    /// A line was indented by more than one level deeper than the line before it, or by a partial
+    /// level.
+    #[error("Unexpected indentation jump at line {line}")]
+    UnexpectedIndentation {
+        /// The 1-based line with the unexpected indentation.
+        line: usize,
+    },
+    /// Some lines are indented with tabs and others with spaces.
+    #[error("Indentation mixes tabs and spaces at line {line}")]
+    MixedIndentation {
+        /// The 1-based line where the indentation first differs from the lines before it.
+        line: usize,
+    },
+    /// The input was empty or only contained whitespace.
+    #[error("Can't parse a {0} from empty input")]
+    EmptyInput(&'static str),
+    /// The input contains a command that isn't supported by the method used to parse it.
+    #[error("{0} commands aren't supported here")]
+    UnsupportedCommand(String),
+}
+
+impl ScriptError {
+    pub(crate) fn from_pest(err: pest::error::Error<Rule>) -> Self {
+        let (line, column) = match err.line_col {
+            LineColLocation::Pos(pos) => pos,
+            LineColLocation::Span(start, _) => start,
+        };
+
+        Self::Syntax {
+            line,
+            column,
+            message: err.to_string(),
+        }
+    }
+
+    /// Create a syntax error for input that was left over after parsing a fragment, starting at
+    /// byte offset `pos` of `input`.
+    pub(crate) fn trailing_input(input: &str, pos: usize) -> Self {
+        let position = Position::new(input, pos).expect("the position is within the input");
+        let err = pest::error::Error::new_from_pos(
+            ErrorVariant::CustomError {
+                message: "unexpected trailing input".to_owned(),
+            },
+            position,
+        );
+
+        Self::from_pest(err)
+    }
+
+    pub(crate) fn unexpected_rule(expected: Rule, found: Rule) -> Self {
+        Self::UnexpectedRule {
+            expected: expected.as_str(),
+            found: found.as_str(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScriptError;
+    use crate::script::{
+        block::Block,
+        command::Command,
+        parser::{Parser, Rule},
+        Script,
+    };
+    use pest::Parser as PestParser;
+
+    #[test]
+    fn test_syntax_error_has_location() {
+        let err = Script::parse("%START%\n|SAY| Hello\n|SAY|\n%END%\n").unwrap_err();
+
+        match err {
+            ScriptError::Syntax { line, column, .. } => assert_eq!((3, 1), (line, column)),
+            err => panic!("expected a syntax error but got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn test_syntax_error_shows_snippet() {
+        let err = Script::parse("%START%\n|SAY| Hello\n|SAY|\n%END%\n").unwrap_err();
+        let message = err.to_string();
+
+        assert!(message.starts_with("Invalid syntax at line 3, column 1:\n"));
+        assert!(message.contains("3 | |SAY|\n  | ^"), "{message}");
+    }
+
+    #[test]
+    fn test_command_without_name_is_a_syntax_error() {
+        let err = Command::parse("ZELDA SAY Hello").unwrap_err();
+
+        assert!(matches!(err, ScriptError::Syntax { line: 1, .. }));
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert_eq!(
+            ScriptError::EmptyInput("Script"),
+            Script::parse("\n").unwrap_err()
+        );
+        assert_eq!(
+            ScriptError::EmptyInput("Block"),
+            Block::parse("").unwrap_err()
+        );
+        assert_eq!(
+            ScriptError::EmptyInput("Command"),
+            Command::parse("  ").unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_unexpected_rule() {
+        let pair = Parser::parse(Rule::Marker, "%START%")
+            .unwrap()
+            .next()
+            .unwrap();
+
+        assert_eq!(
+            ScriptError::UnexpectedRule {
+                expected: "Command",
+                found: "Marker"
+            },
+            Command::try_from(pair).unwrap_err()
+        );
+    }
+
+    #[test]
+    fn test_converts_into_anyhow() {
+        let err: anyhow::Error = Command::parse("").unwrap_err().into();
+
+        assert!(err.downcast_ref::<ScriptError>().is_some());
+    }
+}
@@ -0,0 +1,201 @@
+//! # Includes
+//!
+//! Large projects can factor shared dialogue into separate files and pull them in with the
+//! `|INCLUDE|` command. The suffix of the command is the path of the script to include, relative to
+//! the script that includes it.
+//!
+//! ```text
+//! %START%
+//! |INCLUDE| common/greetings.script
+//! ZELDA |SAY| "Now, to business."
+//! %END%
+//! ```
+//!
+//! Includes are opt-in: they're only expanded by [Script::parse_with_includes], and [Script::parse]
+//! rejects them.
+
+use crate::script::{
+    block::Block,
+    command::INCLUDE_COMMAND,
+    element::TopLevelElement,
+    line::Line,
+    marker::{END_MARKER, START_MARKER},
+    Script,
+};
+use anyhow::{bail, Context};
+use std::path::{Path, PathBuf};
+
+/// Load the script at `path` with `resolver`, expanding any includes it contains.
+pub(crate) fn load<F>(
+    path: &Path,
+    resolver: &mut F,
+    include_stack: &mut Vec<PathBuf>,
+) -> Result<Vec<TopLevelElement>, anyhow::Error>
+where
+    F: FnMut(&Path) -> Result<String, anyhow::Error>,
+{
+    if include_stack.iter().any(|included| included == path) {
+        let cycle = include_stack
+            .iter()
+            .skip_while(|included| *included != path)
+            .chain(std::iter::once(&path.to_path_buf()))
+            .map(|included| included.display().to_string())
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        bail!("Include cycle detected: {cycle}");
+    }
+
+    let script_str =
+        resolver(path).with_context(|| format!("Failed to load script {}", path.display()))?;
+    let script = Script::parse_allowing_includes(&script_str)
+        .with_context(|| format!("Failed to parse script {}", path.display()))?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    include_stack.push(path.to_path_buf());
+    let elements = expand(script.0, dir, resolver, include_stack)?;
+    include_stack.pop();
+
+    Ok(elements)
+}
+
+/// Replace every include in `elements` with the body of the script it refers to.
+fn expand<F>(
+    elements: Vec<TopLevelElement>,
+    dir: &Path,
+    resolver: &mut F,
+    include_stack: &mut Vec<PathBuf>,
+) -> Result<Vec<TopLevelElement>, anyhow::Error>
+where
+    F: FnMut(&Path) -> Result<String, anyhow::Error>,
+{
+    let mut expanded = Vec::with_capacity(elements.len());
+
+    for el in elements {
+        match el {
+            TopLevelElement::Line(Line::Command(command))
+                if command.canonical_name() == INCLUDE_COMMAND =>
+            {
+                let Some(include_path) = command.suffix() else {
+                    bail!("INCLUDE commands require the path of the script to include");
+                };
+
+                let included = load(&dir.join(include_path), resolver, include_stack)?;
+                expanded.extend(included.into_iter().filter(|el| !is_start_or_end(el)));
+            }
+            TopLevelElement::Block(block) => {
+                let inner = expand(block.elements().to_vec(), dir, resolver, include_stack)?;
+                expanded.push(Block::new(inner).into());
+            }
+            el => expanded.push(el),
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// Check whether `elements` contain an include, at any depth.
+pub(crate) fn contains_include(elements: &[TopLevelElement]) -> bool {
+    elements.iter().any(|el| match el {
+        TopLevelElement::Line(Line::Command(command)) => {
+            command.canonical_name() == INCLUDE_COMMAND
+        }
+        TopLevelElement::Block(block) => contains_include(block.elements()),
+        _ => false,
+    })
+}
+
+fn is_start_or_end(el: &TopLevelElement) -> bool {
+    match el {
+        TopLevelElement::Line(Line::Marker(marker)) => {
+            marker.name() == START_MARKER || marker.name() == END_MARKER
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::script::Script;
+    use anyhow::anyhow;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+    use std::path::Path;
+
+    fn resolver_for(
+        files: HashMap<&'static str, &'static str>,
+    ) -> impl FnMut(&Path) -> Result<String, anyhow::Error> {
+        move |path| {
+            files
+                .get(path.to_str().expect("test paths are valid UTF-8"))
+                .map(|contents| contents.to_string())
+                .ok_or_else(|| anyhow!("no such file"))
+        }
+    }
+
+    #[test]
+    fn test_include_is_inlined() {
+        let files = HashMap::from([
+            (
+                "scripts/main.script",
+                "%START%
+ZELDA |SAY| \"Hey there!\"
+    |CHOICE| \"Hello!\"
+        |INCLUDE| common/greetings.script
+ZELDA |SAY| \"Now, to business.\"
+%END%
+",
+            ),
+            (
+                "scripts/common/greetings.script",
+                "%START%
+YOU |SAY| \"Hello!\"
+ZELDA |SAY| \"How are you?\"
+%END%
+",
+            ),
+        ]);
+
+        let script = Script::parse_with_includes("scripts/main.script", resolver_for(files))
+            .expect("includes can be resolved");
+        let expected = "%START%
+ZELDA |SAY| \"Hey there!\"
+    |CHOICE| \"Hello!\"
+        YOU |SAY| \"Hello!\"
+        ZELDA |SAY| \"How are you?\"
+ZELDA |SAY| \"Now, to business.\"
+%END%
+";
+
+        assert_eq!(expected, script.to_string());
+    }
+
+    #[test]
+    fn test_include_cycle_is_an_error() {
+        let files = HashMap::from([
+            ("a.script", "%START%\n|INCLUDE| b.script\n%END%\n"),
+            ("b.script", "%START%\n|INCLUDE| a.script\n%END%\n"),
+        ]);
+
+        let err = Script::parse_with_includes("a.script", resolver_for(files))
+            .expect_err("cyclic includes are rejected");
+
+        assert_eq!(
+            "Include cycle detected: a.script -> b.script -> a.script",
+            format!("{:#}", err.root_cause())
+        );
+    }
+
+    #[test]
+    fn test_plain_parse_rejects_include() {
+        let input = "%START%\n|INCLUDE| common/greetings.script\n%END%\n";
+
+        assert!(Script::parse(input).is_err());
+    }
+
+    #[test]
+    fn test_mixed_case_include_is_recognized() {
+        let input = "%START%\n|Include| common/greetings.script\n%END%\n";
+
+        assert!(Script::parse(input).is_err());
+    }
+}
@@ -1,19 +1,20 @@
 //! # Lines
-//! 
+//!
 //! Lines in a script are either [Markers](#Markers) or [Commands](#Commands). They can include any letters
 //! or symbols, except for pipes _(&nbsp;|&nbsp;)_, as those are used to delimit commands.
 
 use crate::script::{
     command::Command,
+    error::ScriptError,
     marker::Marker,
     parser::{Parser, Rule},
 };
-use anyhow::bail;
 use pest::{iterators::Pair, Parser as PestParser};
-use std::fmt;
+use std::{fmt, str::FromStr};
 
 /// A line in a script.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Line {
     /// A [Command].
     Command(Command),
@@ -49,8 +50,12 @@ impl Line {
     }
 
     /// Create a new [Line] from a string.
-    pub fn parse(line_str: &str) -> Result<Self, anyhow::Error> {
-        let mut pairs = Parser::parse(Rule::Line, line_str)?;
+    pub fn parse(line_str: &str) -> Result<Self, ScriptError> {
+        if line_str.trim().is_empty() {
+            return Err(ScriptError::EmptyInput("Line"));
+        }
+
+        let mut pairs = Parser::parse(Rule::Line, line_str).map_err(ScriptError::from_pest)?;
         let pair = pairs.next().expect("a pair exists");
         assert_eq!(pairs.next(), None);
 
@@ -58,8 +63,16 @@ impl Line {
     }
 }
 
+impl FromStr for Line {
+    type Err = ScriptError;
+
+    fn from_str(line_str: &str) -> Result<Self, Self::Err> {
+        Self::parse(line_str)
+    }
+}
+
 impl TryFrom<Pair<'_, Rule>> for Line {
-    type Error = anyhow::Error;
+    type Error = ScriptError;
 
     fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
         match pair.as_rule() {
@@ -69,12 +82,12 @@ impl TryFrom<Pair<'_, Rule>> for Line {
                 assert_eq!(pairs.next(), None);
 
                 match pair.as_rule() {
-                    Rule::Command => Command::parse(pair.as_str()).map(Self::Command),
-                    Rule::Marker => Marker::parse(pair.as_str()).map(Self::Marker),
+                    Rule::Command => Command::try_from(pair).map(Self::Command),
+                    Rule::Marker => Marker::try_from(pair).map(Self::Marker),
                     _ => unreachable!("Lines can't contain anything other than commands, comments, markers, or blank lines"),
                 }
             }
-            _ => bail!("Pair is not a line: {:#?}", pair),
+            rule => Err(ScriptError::unexpected_rule(Rule::Line, rule)),
         }
     }
 }
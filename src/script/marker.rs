@@ -5,48 +5,120 @@
 //! symbols. By using the [|GOTO| command], the flow of dialogue can be
 //! redirected to just after a marker.
 
-use crate::script::parser::Rule;
-use anyhow::bail;
-use pest::iterators::Pair;
-use std::{borrow::Cow, fmt};
+use crate::script::{
+    error::ScriptError,
+    parser::{Parser, Rule},
+};
+use pest::{iterators::Pair, Parser as PestParser};
+use std::{borrow::Cow, fmt, str::FromStr};
+
+/// The name of the marker every script starts with.
+pub const START_MARKER: &str = "START";
+/// The name of the marker every script ends with.
+pub const END_MARKER: &str = "END";
+
+/// Check whether `name` is a valid marker name, written in ALL-CAPS-KEBAB-CASE.
+pub fn is_valid_marker_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '-')
+}
 
 /// A marker that can be used as a destination for `GOTO` commands.
-#[derive(Debug, PartialEq, Eq, Clone)]
-pub struct Marker(Cow<'static, str>);
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Marker {
+    name: Cow<'static, str>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    line: Option<usize>,
+}
+
+impl PartialEq for Marker {
+    fn eq(&self, other: &Self) -> bool {
+        // The line is deliberately ignored, like for commands.
+        self.name == other.name
+    }
+}
+
+impl Eq for Marker {}
 
 impl fmt::Display for Marker {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "%{}%", self.0)
+        write!(f, "%{}%", self.name)
     }
 }
 
 impl Marker {
     /// Get the name of the marker.
     pub fn name(&self) -> &str {
-        &self.0
+        &self.name
+    }
+
+    /// Get the 1-based line this `Marker` was parsed from. Returns `None` for markers that
+    /// weren't parsed from a script.
+    pub fn line(&self) -> Option<usize> {
+        self.line
     }
 }
 
 impl Marker {
-    /// Create a new `Marker` from a string.
+    /// Create a new `Marker` from a string. The name isn't checked, see [is_valid_marker_name].
     pub fn new<T: Into<Cow<'static, str>>>(name: T) -> Self {
-        Self(name.into())
+        Self {
+            name: name.into(),
+            line: None,
+        }
     }
 
-    /// Parse a `Marker` from a string.
-    pub fn parse(marker_str: &str) -> Result<Self, anyhow::Error> {
-        let marker = marker_str.trim_start_matches('%').trim_end_matches('%');
-        Ok(Self(marker.to_owned().into()))
+    /// Parse a `Marker` from a string. The name must be written in ALL-CAPS-KEBAB-CASE.
+    pub fn parse(marker_str: &str) -> Result<Self, ScriptError> {
+        let marker_str = marker_str.trim();
+        if marker_str.is_empty() {
+            return Err(ScriptError::EmptyInput("Marker"));
+        }
+
+        let mut pairs = Parser::parse(Rule::Marker, marker_str).map_err(ScriptError::from_pest)?;
+        let pair = pairs.next().expect("a pair exists");
+        assert_eq!(pairs.next(), None);
+
+        let end = pair.as_span().end();
+        if end < marker_str.len() {
+            return Err(ScriptError::trailing_input(marker_str, end));
+        }
+
+        pair.try_into()
+    }
+}
+
+impl FromStr for Marker {
+    type Err = ScriptError;
+
+    fn from_str(marker_str: &str) -> Result<Self, Self::Err> {
+        Self::parse(marker_str)
     }
 }
 
 impl TryFrom<Pair<'_, Rule>> for Marker {
-    type Error = anyhow::Error;
+    type Error = ScriptError;
 
     fn try_from(pair: Pair<'_, Rule>) -> Result<Self, Self::Error> {
         match pair.as_rule() {
-            Rule::Marker => Marker::parse(pair.as_str()),
-            _ => bail!("Pair is not a marker: {:#?}", pair),
+            Rule::Marker => {
+                let (line, _) = pair.line_col();
+                let name = pair.into_inner().next().expect("all markers have a name");
+                debug_assert!(
+                    is_valid_marker_name(name.as_str()),
+                    "the grammar only accepts valid marker names"
+                );
+
+                Ok(Self {
+                    name: name.as_str().to_owned().into(),
+                    line: Some(line),
+                })
+            }
+            rule => Err(ScriptError::unexpected_rule(Rule::Marker, rule)),
         }
     }
 }
@@ -54,6 +126,7 @@ impl TryFrom<Pair<'_, Rule>> for Marker {
 #[cfg(test)]
 mod tests {
     use super::Marker;
+    use crate::script::error::ScriptError;
 
     #[test]
     fn test_marker_parse() {
@@ -61,6 +134,34 @@ mod tests {
         assert_eq!(marker.name(), "START");
     }
 
+    #[test]
+    fn test_marker_names_must_be_all_caps_kebab_case() {
+        assert_eq!("GO-BACK", Marker::parse("%GO-BACK%").unwrap().name());
+
+        for input in ["%go-back%", "%has spaces%", "%GO_BACK%"] {
+            assert!(
+                matches!(Marker::parse(input), Err(ScriptError::Syntax { .. })),
+                "{input} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_marker_is_an_error() {
+        assert!(matches!(
+            Marker::parse("%START").unwrap_err(),
+            ScriptError::Syntax { .. }
+        ));
+        assert!(matches!(
+            Marker::parse("%START% %END%").unwrap_err(),
+            ScriptError::Syntax { column: 8, .. }
+        ));
+        assert_eq!(
+            ScriptError::EmptyInput("Marker"),
+            Marker::parse("").unwrap_err()
+        );
+    }
+
     #[test]
     fn test_round_trip() {
         let marker = Marker::parse("%START%").unwrap();
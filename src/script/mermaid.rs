@@ -0,0 +1,206 @@
+//! # Mermaid
+//!
+//! Export a script as a [Mermaid](https://mermaid.js.org/) flowchart, for embedding its structure in
+//! Markdown documents. See [Script::to_mermaid](crate::script::Script::to_mermaid).
+
+use crate::script::{
+    command::{Command, CHOICE_COMMAND, SAY_COMMAND},
+    element::TopLevelElement,
+    line::Line,
+};
+use std::fmt::Write;
+
+/// The longest a node's label can be before it's truncated with an ellipsis, in characters.
+const MAX_LABEL_LEN: usize = 40;
+
+/// Render `elements` as a top-down Mermaid flowchart.
+pub(crate) fn to_mermaid(elements: &[TopLevelElement]) -> String {
+    let mut flowchart = Flowchart {
+        out: String::from("flowchart TD\n"),
+        next_id: 0,
+    };
+    flowchart.render(elements, Vec::new());
+
+    flowchart.out
+}
+
+struct Flowchart {
+    out: String,
+    next_id: usize,
+}
+
+impl Flowchart {
+    /// Render `elements`, connecting the first of them to the nodes in `preds`. Returns the nodes
+    /// that flow continues from after the last element.
+    fn render(&mut self, elements: &[TopLevelElement], mut preds: Vec<String>) -> Vec<String> {
+        let mut elements = elements.iter().peekable();
+
+        while let Some(el) = elements.next() {
+            match el {
+                TopLevelElement::Line(Line::Marker(marker)) => {
+                    let id = marker_id(marker.name());
+                    self.node(&id, "([\"", &format!("%{}%", marker.name()), "\"])");
+                    self.edges(&preds, &id);
+                    preds = vec![id];
+                }
+                TopLevelElement::Line(Line::Command(command)) if command.is_choice() => {
+                    let mut exits = Vec::new();
+                    let mut next = Some(command);
+
+                    // Every choice in a group follows the same nodes, and flow continues after
+                    // whichever choice's body runs.
+                    while let Some(choice) = next.take() {
+                        let id = self.command_node(choice);
+                        self.edges(&preds, &id);
+
+                        match elements.peek() {
+                            Some(TopLevelElement::Block(body)) => {
+                                elements.next();
+                                exits.extend(self.render(body.elements(), vec![id]));
+                            }
+                            _ => exits.push(id),
+                        }
+
+                        if let Some(TopLevelElement::Line(Line::Command(command))) = elements.peek()
+                        {
+                            if command.is_choice() {
+                                elements.next();
+                                next = Some(command);
+                            }
+                        }
+                    }
+
+                    preds = exits;
+                }
+                TopLevelElement::Line(Line::Command(command)) => {
+                    let id = self.command_node(command);
+                    self.edges(&preds, &id);
+
+                    match command.goto_target() {
+                        Some(target) if command.is_goto() => {
+                            let _ = writeln!(self.out, "    {id} -.-> {}", marker_id(target));
+                            if let Some(fallback) = command.goto_fallback() {
+                                let _ = writeln!(self.out, "    {id} -.-> {}", marker_id(fallback));
+                            }
+                            // Flow only falls through a GOTO when its condition doesn't hold.
+                            preds = match command.condition() {
+                                Some(_) => vec![id],
+                                None => Vec::new(),
+                            };
+                        }
+                        _ => preds = vec![id],
+                    }
+                }
+                TopLevelElement::Block(block) => preds = self.render(block.elements(), preds),
+                TopLevelElement::Comment(_) => {}
+            }
+        }
+
+        preds
+    }
+
+    fn command_node(&mut self, command: &Command) -> String {
+        let id = format!("n{}", self.next_id);
+        self.next_id += 1;
+
+        let canonical_name = command.canonical_name();
+        match canonical_name.as_ref() {
+            SAY_COMMAND => {
+                let text = command.suffix().unwrap_or_default();
+                let label = match command.prefix() {
+                    Some(speaker) => format!("{speaker}: {text}"),
+                    None => text.to_owned(),
+                };
+                self.node(&id, "[\"", &label, "\"]");
+            }
+            CHOICE_COMMAND => {
+                let label = command.choice_text().unwrap_or_default();
+                self.node(&id, "{{\"", label, "\"}}");
+            }
+            _ => self.node(&id, "[\"", &command.to_string(), "\"]"),
+        }
+
+        id
+    }
+
+    fn node(&mut self, id: &str, open: &str, label: &str, close: &str) {
+        let _ = writeln!(
+            self.out,
+            "    {id}{open}{}{close}",
+            escape(&truncate(label))
+        );
+    }
+
+    fn edges(&mut self, preds: &[String], to: &str) {
+        for pred in preds {
+            let _ = writeln!(self.out, "    {pred} --> {to}");
+        }
+    }
+}
+
+fn marker_id(name: &str) -> String {
+    format!("marker_{}", name.replace('-', "_"))
+}
+
+fn truncate(label: &str) -> String {
+    if label.chars().count() <= MAX_LABEL_LEN {
+        return label.to_owned();
+    }
+
+    let truncated: String = label.chars().take(MAX_LABEL_LEN - 1).collect();
+    format!("{}…", truncated.trim_end())
+}
+
+fn escape(label: &str) -> String {
+    label.replace('"', "#quot;").replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::script::Script;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_to_mermaid() {
+        let input = std::fs::read_to_string("example_scripts/daisy-and-luigi.script")
+            .expect("example script exists");
+        let script = Script::parse(&input).expect("a script can be parsed");
+
+        let expected = r##"flowchart TD
+    marker_START(["%START%"])
+    n0["DAISY: #quot;This is a test.#quot;"]
+    marker_START --> n0
+    n1["DAISY: #quot;You got that?#quot;"]
+    n0 --> n1
+    n2{{"#quot;Come again?#quot;"}}
+    n1 --> n2
+    n3["LUIGI: #quot;Come again?#quot;"]
+    n2 --> n3
+    n4["|GOTO| %START%"]
+    n3 --> n4
+    n4 -.-> marker_START
+    n5{{"#quot;Ah, yes. Thank you.#quot;"}}
+    n1 --> n5
+    n6["LUIGI: #quot;Ah, yes. Thank you.#quot;"]
+    n5 --> n6
+    n7["DAISY: #quot;You're welcome.#quot;"]
+    n6 --> n7
+    marker_END(["%END%"])
+    n7 --> marker_END
+"##;
+        assert_eq!(expected, script.to_mermaid());
+    }
+
+    #[test]
+    fn test_long_text_is_truncated() {
+        let input = "%START%
+ZELDA |SAY| \"Well of course it did! Do you think I'm some two-bit hack?\"
+%END%
+";
+        let script = Script::parse(input).expect("a script can be parsed");
+
+        assert!(script
+            .to_mermaid()
+            .contains(r##"n0["ZELDA: #quot;Well of course it did! Do you t…"]"##));
+    }
+}
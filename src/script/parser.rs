@@ -22,16 +22,25 @@ impl Rule {
         match self {
             AllowedSymbols => "AllowedSymbols",
             Char => "Char",
+            Escape => "Escape",
             Text => "Text",
             Marker => "Marker",
             MarkerName => "MarkerName",
             Comment => "Comment",
+            CommentText => "CommentText",
             Prefix => "Prefix",
             Command => "Command",
+            TrailingComment => "TrailingComment",
+            TrailingCommentStart => "TrailingCommentStart",
             CommandName => "CommandName",
+            TripleQuotedText => "TripleQuotedText",
+            Variable => "Variable",
+            Operator => "Operator",
+            Condition => "Condition",
             Line => "Line",
             Block => "Block",
             Script => "Script",
+            EOI => "EOI",
         }
     }
 }
@@ -0,0 +1,115 @@
+//! # Subtitles
+//!
+//! Export the `SAY` commands of a playthrough as [SRT](https://en.wikipedia.org/wiki/SubRip) subtitle
+//! cues, for captioning recorded dialogue. The duration of each cue is estimated from the number of
+//! words in it.
+
+use crate::script::command::Command;
+use std::fmt::Write;
+
+/// Options for [to_srt].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SrtOptions {
+    /// The reading speed used to estimate how long each cue stays on screen.
+    pub words_per_minute: u32,
+    /// The shortest time a cue stays on screen, in milliseconds.
+    pub min_duration_ms: u64,
+}
+
+impl Default for SrtOptions {
+    fn default() -> Self {
+        Self {
+            words_per_minute: 180,
+            min_duration_ms: 1000,
+        }
+    }
+}
+
+/// Render the `SAY` commands in `commands` as SRT cues, in order. Other commands are skipped.
+///
+/// The speaker of a `SAY` becomes a prefix of its cue, like `ZELDA: "Hey there!"`.
+pub fn to_srt<'a>(commands: impl IntoIterator<Item = &'a Command>, options: &SrtOptions) -> String {
+    let mut srt = String::new();
+    let mut start_ms = 0;
+
+    let says = commands.into_iter().filter(|command| command.is_say());
+    for (index, say) in says.enumerate() {
+        let text = say.suffix().unwrap_or_default();
+        let words = text.split_whitespace().count() as u64;
+        let duration_ms = (words * 60_000 / u64::from(options.words_per_minute.max(1)))
+            .max(options.min_duration_ms);
+        let end_ms = start_ms + duration_ms;
+
+        let _ = writeln!(srt, "{}", index + 1);
+        let _ = writeln!(
+            srt,
+            "{} --> {}",
+            format_timestamp(start_ms),
+            format_timestamp(end_ms)
+        );
+        match say.prefix() {
+            Some(speaker) => {
+                let _ = writeln!(srt, "{speaker}: {text}");
+            }
+            None => {
+                let _ = writeln!(srt, "{text}");
+            }
+        }
+        srt.push('\n');
+
+        start_ms = end_ms;
+    }
+
+    srt
+}
+
+/// Format a time in milliseconds as an SRT timestamp, like `00:01:02,345`.
+fn format_timestamp(ms: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{to_srt, SrtOptions};
+    use crate::script::{element::TopLevelElement, line::Line, Script};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_to_srt() {
+        let input = std::fs::read_to_string("example_scripts/three-line.script")
+            .expect("example script exists");
+        let script = Script::parse(&input).expect("a script can be parsed");
+        let commands = script.0.iter().filter_map(|el| match el {
+            TopLevelElement::Line(Line::Command(command)) => Some(command),
+            _ => None,
+        });
+
+        let srt = to_srt(commands, &SrtOptions::default());
+
+        let expected = "1
+00:00:00,000 --> 00:00:01,000
+ASHLEY: \"Did it work?\"
+
+2
+00:00:01,000 --> 00:00:05,000
+ZELDA: \"Well of course it did! Do you think I'm some two-bit hack?\"
+
+3
+00:00:05,000 --> 00:00:08,666
+ASHLEY: \"Well, no; But isn't this the first such system you've written?\"
+
+";
+        assert_eq!(expected, srt);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!("01:02:03,004", super::format_timestamp(3_723_004));
+    }
+}
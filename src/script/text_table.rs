@@ -0,0 +1,44 @@
+//! # Text tables
+//!
+//! Text tables hold translations of a script's text, so that one script can be shared across
+//! locales. Translations are looked up by the stable key of a command, like the key of a `CHOICE`:
+//!
+//! ```text
+//! |CHOICE| [accept] "I accept"
+//! ```
+//!
+//! When a table has no translation for a command, the text embedded in the script is used instead.
+//! See [Command::localized_suffix](crate::script::command::Command::localized_suffix).
+
+use std::collections::HashMap;
+
+/// Translations of a script's text, by locale and key.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct TextTable {
+    locales: HashMap<String, HashMap<String, String>>,
+}
+
+impl TextTable {
+    /// Create a new, empty [TextTable].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the translation of the text with `key` for `locale`, replacing any existing one.
+    pub fn insert(
+        &mut self,
+        locale: impl Into<String>,
+        key: impl Into<String>,
+        text: impl Into<String>,
+    ) {
+        self.locales
+            .entry(locale.into())
+            .or_default()
+            .insert(key.into(), text.into());
+    }
+
+    /// Get the translation of the text with `key` for `locale`, if there is one.
+    pub fn get(&self, locale: &str, key: &str) -> Option<&str> {
+        self.locales.get(locale)?.get(key).map(String::as_str)
+    }
+}